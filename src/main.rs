@@ -2,17 +2,22 @@
 
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::File,
-    io::{BufRead, BufReader, Seek, SeekFrom},
+    io::{BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
-    sync::mpsc::{Receiver, RecvTimeoutError, channel},
+    sync::{
+        Mutex,
+        mpsc::{Receiver, RecvTimeoutError, channel},
+    },
     time::Duration,
 };
 
 use anyhow::{Context, Result, anyhow};
 use colored::Colorize;
-use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, Timelike, Utc};
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, NaiveDate, SecondsFormat, Timelike, Utc,
+};
 use chrono_tz::Tz;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use crossterm::{
@@ -42,44 +47,109 @@ const PROJECTS_DIR: &str = "projects";
 const DEFAULT_BLOCK_HOURS: u32 = 5;
 const DEFAULT_RECENT_DAYS: u32 = 3;
 const DEFAULT_REFRESH_SECONDS: u64 = 5;
-
-#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+const DEFAULT_AUDIT_TOLERANCE_PERCENT: f64 = 1.0;
+/// Events without a logged cost listed by example in the audit JSON
+const MISSING_COST_SAMPLE_SIZE: usize = 10;
+const EVENT_INDEX_VERSION: u32 = 10;
+/// Bytes at each end of an indexed prefix that are checked before reading only the tail
+const PREFIX_DIGEST_BYTES: u64 = 4096;
+const LITELLM_PRICING_URL: &str =
+    "https://raw.githubusercontent.com/BerriAI/litellm/main/model_prices_and_context_window.json";
+/// Fetched pricing is refreshed after this long; imported files never expire
+const PRICING_CACHE_TTL_HOURS: i64 = 24;
+/// Requests whose prompt exceeds this many tokens are billed at long-context rates
+const LONG_CONTEXT_THRESHOLD_TOKENS: u64 = 200_000;
+const BURN_RATE_WINDOW_MINUTES: i64 = 30;
+const STATUSLINE_CACHE_TTL_SECONDS: i64 = 60;
+/// Claude Code writes the hook payload at once; stdin that stays silent longer is not a hook
+const STATUSLINE_HOOK_TIMEOUT_MS: u64 = 500;
+const STATUSLINE_HOOK_MAX_BYTES: u64 = 1024 * 1024;
+
+#[derive(Copy, Clone, Debug, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 enum Order {
     Asc,
+    #[default]
     Desc,
 }
 
-impl Default for Order {
-    fn default() -> Self {
-        Order::Desc
-    }
-}
-
-#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 enum WeekStart {
     Sunday,
+    #[default]
     Monday,
 }
 
-impl Default for WeekStart {
-    fn default() -> Self {
-        WeekStart::Monday
-    }
-}
-
-#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 enum CostMode {
+    /// Logged `costUSD` unless it is missing or zero, otherwise the calculated cost
+    #[default]
     Auto,
+    /// Logged `costUSD` whenever present, even $0, otherwise the calculated cost
     PreferField,
+    /// Always the calculated cost
     Calculate,
 }
 
-impl Default for CostMode {
-    fn default() -> Self {
-        CostMode::Auto
+/// How usage from sidechains (Task-tool subagents) enters reports.
+#[derive(Copy, Clone, Debug, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SidechainMode {
+    /// Count main-thread and subagent usage together
+    #[default]
+    Include,
+    /// Only subagent usage
+    Only,
+    /// Only main-thread usage
+    Exclude,
+    /// Count both and report each side separately
+    Split,
+}
+
+impl SidechainMode {
+    fn includes(self, ev: &UsageEvent) -> bool {
+        match self {
+            SidechainMode::Only => ev.is_sidechain,
+            SidechainMode::Exclude => !ev.is_sidechain,
+            SidechainMode::Include | SidechainMode::Split => true,
+        }
+    }
+}
+
+/// Claude subscription tier; `custom` takes its fee from `monthly_price`.
+#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+enum PlanTier {
+    #[serde(rename = "pro")]
+    Pro,
+    #[serde(rename = "max-5x")]
+    #[value(name = "max-5x")]
+    Max5x,
+    #[serde(rename = "max-20x")]
+    #[value(name = "max-20x")]
+    Max20x,
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+impl PlanTier {
+    fn monthly_price(self) -> Option<f64> {
+        match self {
+            PlanTier::Pro => Some(20.0),
+            PlanTier::Max5x => Some(100.0),
+            PlanTier::Max20x => Some(200.0),
+            PlanTier::Custom => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PlanTier::Pro => "Pro",
+            PlanTier::Max5x => "Max 5x",
+            PlanTier::Max20x => "Max 20x",
+            PlanTier::Custom => "Custom",
+        }
     }
 }
 
@@ -114,14 +184,38 @@ struct Cli {
     #[arg(long, global = true, num_args = 0..=1, default_missing_value = "true")]
     breakdown: Option<bool>,
 
-    /// Offline mode (pricing stays local; currently always local)
+    /// Offline mode (use bundled pricing, never fetch)
     #[arg(long, global = true, num_args = 0..=1, default_missing_value = "true")]
     offline: Option<bool>,
 
-    /// Cost calculation mode (auto: use costUSD if present, otherwise calculate; calculate: always calculate; prefer_field: only use costUSD when present)
+    /// Cost calculation mode (auto: use costUSD unless it is missing or zero, otherwise calculate; prefer-field: use costUSD whenever present; calculate: always calculate)
     #[arg(long, global = true, value_enum)]
     cost_mode: Option<CostMode>,
 
+    /// Skip the on-disk event index and re-parse every JSONL file
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "true")]
+    no_cache: Option<bool>,
+
+    /// Fail instead of reporting when usage of a model without pricing would count as $0.00
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "true")]
+    strict_pricing: Option<bool>,
+
+    /// Also show costs in this currency (ISO code, e.g. EUR), using the exchange-rate file
+    #[arg(long, global = true)]
+    currency: Option<String>,
+
+    /// Exchange-rate file (JSON, units per USD). Defaults to ~/.config/ccusage/exchange-rates.json
+    #[arg(long, global = true)]
+    exchange_rates: Option<PathBuf>,
+
+    /// Subagent (sidechain) usage: include (default), only, exclude, or split into its own figures
+    #[arg(long, global = true, value_enum)]
+    sidechain: Option<SidechainMode>,
+
+    /// Show project paths relative to this directory (default: relative to home as ~/...)
+    #[arg(long, global = true)]
+    project_root: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -132,14 +226,107 @@ enum Command {
     Daily(RangeArgs),
     /// Weekly usage aggregated by week
     Weekly(WeeklyArgs),
-    /// Monthly usage aggregated by month
-    Monthly(RangeArgs),
+    /// Monthly usage aggregated by month or billing cycle
+    Monthly(MonthlyArgs),
     /// Session totals aggregated by session id
     Sessions(RangeArgs),
     /// 5-hour billing window view
     Blocks(BlocksArgs),
-    /// Compact statusline summary for most recent day
-    Statusline(RangeArgs),
+    /// Compact statusline summary; reads Claude Code's statusLine JSON from stdin when piped
+    Statusline(StatuslineArgs),
+    /// Manage the on-disk event index
+    Cache(CacheArgs),
+    /// Inspect, refresh or import model pricing
+    Pricing(PricingArgs),
+    /// Compare logged costUSD with costs calculated from the pricing table
+    Audit(AuditArgs),
+    /// API-equivalent cost against the subscription fee per billing cycle
+    Plan(PlanArgs),
+    /// Usage grouped by git repository and branch
+    Branches(RangeArgs),
+    /// Tool invocations with the tokens and cost of the turns that called them
+    Tools(RangeArgs),
+    /// API errors, overloads and usage-limit hits per day and block
+    Errors(ErrorsArgs),
+    /// Usage and cache-hit ratio per Claude Code version
+    Versions(RangeArgs),
+}
+
+#[derive(Args, Debug, Clone, Default)]
+struct ErrorsArgs {
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Length of a billing block in hours (default 5)
+    #[arg(long)]
+    session_length_hours: Option<u32>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+struct AuditArgs {
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Flag models whose logged and calculated costs differ by more than this percentage
+    #[arg(long)]
+    tolerance: Option<f64>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+struct PlanArgs {
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Subscription tier (overrides the config's `plan.name`)
+    #[arg(long, value_enum)]
+    plan: Option<PlanTier>,
+
+    /// Monthly fee in USD; required for `custom`, overrides the tier's list price otherwise
+    #[arg(long)]
+    monthly_price: Option<f64>,
+
+    /// Day of the month the subscription renews (1-31; clamped to short months)
+    #[arg(long)]
+    renewal_day: Option<u32>,
+}
+
+#[derive(Args, Debug, Clone)]
+struct PricingArgs {
+    #[command(subcommand)]
+    action: PricingAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum PricingAction {
+    /// Print the rates used for a model and the pricing key that matched
+    Show {
+        model: String,
+        /// Day whose rates to show (YYYY-MM-DD, default today)
+        #[arg(long)]
+        date: Option<String>,
+    },
+    /// Fetch LiteLLM pricing now, ignoring the cache TTL
+    Update,
+    /// Use a downloaded LiteLLM pricing file instead of fetching it
+    Import { path: PathBuf },
+    /// List known Claude models and their rates
+    List,
+}
+
+#[derive(Args, Debug, Clone)]
+struct CacheArgs {
+    #[command(subcommand)]
+    action: CacheAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum CacheAction {
+    /// Delete the event index and the statusline cache
+    Clear {
+        /// Also delete the cached pricing data, including a `pricing import`
+        #[arg(long)]
+        pricing: bool,
+    },
 }
 
 #[derive(Args, Debug, Clone, Default)]
@@ -152,10 +339,14 @@ struct RangeArgs {
     #[arg(long)]
     until: Option<String>,
 
-    /// Only include a specific project name (derived from path under projects/)
+    /// Only include one project: its label, working directory or directory name under projects/
     #[arg(long)]
     project: Option<String>,
 
+    /// Only include usage recorded on this git branch
+    #[arg(long)]
+    branch: Option<String>,
+
     /// Group daily/weekly/monthly output by project/instance as well
     #[arg(long, num_args = 0..=1, default_missing_value = "true")]
     instances: Option<bool>,
@@ -175,6 +366,26 @@ struct WeeklyArgs {
     start_of_week: Option<WeekStart>,
 }
 
+#[derive(Args, Debug, Clone, Default)]
+struct MonthlyArgs {
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Day of the month billing cycles start on (default 1: calendar months)
+    #[arg(long = "cycle-start-day")]
+    cycle_start_day: Option<u32>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+struct StatuslineArgs {
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// Output template, e.g. "{model} {session_cost|>5:red|green} | {block_remaining} left"
+    #[arg(long)]
+    format: Option<String>,
+}
+
 #[derive(Args, Debug, Clone, Default)]
 struct BlocksArgs {
     #[command(flatten)]
@@ -212,14 +423,22 @@ struct DefaultsConfig {
     breakdown: Option<bool>,
     offline: Option<bool>,
     cost_mode: Option<CostMode>,
+    no_cache: Option<bool>,
+    strict_pricing: Option<bool>,
     timezone: Option<String>,
     locale: Option<String>,
+    currency: Option<String>,
+    exchange_rates: Option<PathBuf>,
+    project_root: Option<PathBuf>,
+    sidechain: Option<SidechainMode>,
     order: Option<Order>,
     instances: Option<bool>,
     since: Option<String>,
     until: Option<String>,
     project: Option<String>,
+    branch: Option<String>,
     start_of_week: Option<WeekStart>,
+    cycle_start_day: Option<u32>,
     token_limit: Option<u64>,
     recent_days: Option<u32>,
     session_length_hours: Option<u32>,
@@ -235,13 +454,186 @@ struct CommandConfigs {
     monthly: Option<DefaultsConfig>,
     sessions: Option<DefaultsConfig>,
     blocks: Option<DefaultsConfig>,
-    statusline: Option<DefaultsConfig>,
+    statusline: Option<StatuslineConfig>,
+    audit: Option<AuditConfig>,
+    plan: Option<DefaultsConfig>,
+    branches: Option<DefaultsConfig>,
+    tools: Option<DefaultsConfig>,
+    errors: Option<DefaultsConfig>,
+    versions: Option<DefaultsConfig>,
+}
+
+/// `commands.statusline`: the usual command options plus the output template.
+#[derive(Default, Deserialize)]
+struct StatuslineConfig {
+    #[serde(flatten)]
+    common: DefaultsConfig,
+    format: Option<String>,
+}
+
+/// `commands.audit`: the usual command options plus the audit's tolerance.
+#[derive(Default, Deserialize)]
+struct AuditConfig {
+    #[serde(flatten)]
+    common: DefaultsConfig,
+    /// Tolerance in percent
+    tolerance: Option<f64>,
 }
 
 #[derive(Default, Deserialize)]
 struct FileConfig {
     defaults: Option<DefaultsConfig>,
     commands: Option<CommandConfigs>,
+    pricing: Option<PricingConfig>,
+    plan: Option<PlanConfig>,
+}
+
+/// The subscription the usage is compared against.
+#[derive(Default, Deserialize)]
+struct PlanConfig {
+    name: Option<PlanTier>,
+    /// Monthly fee in USD
+    monthly_price: Option<f64>,
+    /// Day of the month the subscription renews
+    renewal_day: Option<u32>,
+}
+
+/// A model's rates in the config file: one entry, or dated entries over time.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigPricing {
+    Single(ConfigRates),
+    Dated(Vec<ConfigRates>),
+}
+
+/// Rates set in the config file. Each one replaces the matching rate of the
+/// pricing data entry; rates left out keep the data's value.
+#[derive(Clone, Default, Deserialize)]
+struct ConfigRates {
+    effective_from: Option<NaiveDate>,
+    effective_until: Option<NaiveDate>,
+    input_per_million: Option<f64>,
+    output_per_million: Option<f64>,
+    cache_create_per_million: Option<f64>,
+    cache_create_1h_per_million: Option<f64>,
+    cache_read_per_million: Option<f64>,
+    input_above_200k_per_million: Option<f64>,
+    output_above_200k_per_million: Option<f64>,
+    cache_create_above_200k_per_million: Option<f64>,
+    cache_create_1h_above_200k_per_million: Option<f64>,
+    cache_read_above_200k_per_million: Option<f64>,
+}
+
+impl ConfigRates {
+    fn in_effect(&self, date: NaiveDate) -> bool {
+        self.effective_from.is_none_or(|from| from <= date)
+            && self.effective_until.is_none_or(|until| date <= until)
+    }
+
+    /// These rates on top of `base`; a model the data doesn't know starts from zero.
+    fn over(&self, base: Option<&Pricing>) -> Pricing {
+        let base = base.cloned().unwrap_or_default();
+        Pricing {
+            effective_from: self.effective_from,
+            effective_until: self.effective_until,
+            input_per_million: self.input_per_million.unwrap_or(base.input_per_million),
+            output_per_million: self.output_per_million.unwrap_or(base.output_per_million),
+            cache_create_per_million: self
+                .cache_create_per_million
+                .unwrap_or(base.cache_create_per_million),
+            cache_create_1h_per_million: self
+                .cache_create_1h_per_million
+                .or(base.cache_create_1h_per_million),
+            cache_read_per_million: self
+                .cache_read_per_million
+                .unwrap_or(base.cache_read_per_million),
+            input_above_200k_per_million: self
+                .input_above_200k_per_million
+                .or(base.input_above_200k_per_million),
+            output_above_200k_per_million: self
+                .output_above_200k_per_million
+                .or(base.output_above_200k_per_million),
+            cache_create_above_200k_per_million: self
+                .cache_create_above_200k_per_million
+                .or(base.cache_create_above_200k_per_million),
+            cache_create_1h_above_200k_per_million: self
+                .cache_create_1h_above_200k_per_million
+                .or(base.cache_create_1h_above_200k_per_million),
+            cache_read_above_200k_per_million: self
+                .cache_read_above_200k_per_million
+                .or(base.cache_read_above_200k_per_million),
+        }
+    }
+}
+
+/// Local pricing entries; these win over remote and bundled data.
+#[derive(Default, Deserialize)]
+struct PricingConfig {
+    /// Replaces the rates of a known model or adds a new one
+    #[serde(default)]
+    models: HashMap<String, ConfigPricing>,
+    /// Prices a model name as another entry, e.g. a proxy name as its upstream model
+    #[serde(default)]
+    aliases: HashMap<String, String>,
+}
+
+/// Units of a currency per US dollar: one rate, or dated rates that each apply
+/// from their `effective_from` day until the next one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExchangeRate {
+    Fixed(f64),
+    Dated(Vec<DatedExchangeRate>),
+}
+
+#[derive(Deserialize)]
+struct DatedExchangeRate {
+    effective_from: NaiveDate,
+    rate: f64,
+}
+
+/// Display currency for converted costs, with its rates sorted by start day.
+struct Currency {
+    code: String,
+    rates: Vec<(NaiveDate, f64)>,
+    /// Report timezone; rates take effect on its calendar days.
+    tz: Tz,
+}
+
+impl Currency {
+    /// The latest rate in effect on `date`; the earliest rate also covers prior days.
+    fn rate_on(&self, date: NaiveDate) -> f64 {
+        self.rates
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= date)
+            .or(self.rates.first())
+            .map_or(1.0, |(_, rate)| *rate)
+    }
+
+    /// Amount in micro-units of this currency, at the rate for the local day of `at`.
+    fn convert(&self, usd: Money, at: DateTime<Utc>) -> Money {
+        usd.scaled(self.rate_on(at.with_timezone(&self.tz).date_naive()))
+    }
+
+    fn minor_digits(&self) -> u32 {
+        match self.code.as_str() {
+            "JPY" | "KRW" | "VND" | "CLP" | "ISK" => 0,
+            _ => 2,
+        }
+    }
+
+    fn symbol(&self) -> Option<&'static str> {
+        match self.code.as_str() {
+            "USD" => Some("$"),
+            "EUR" => Some("€"),
+            "GBP" => Some("£"),
+            "JPY" | "CNY" => Some("¥"),
+            "KRW" => Some("₩"),
+            "INR" => Some("₹"),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -250,6 +642,10 @@ struct FileConfig {
 struct RawRecord {
     #[serde(default)]
     cwd: Option<String>,
+    #[serde(rename = "gitBranch", default)]
+    git_branch: Option<String>,
+    #[serde(rename = "isSidechain", default)]
+    is_sidechain: Option<bool>,
     #[serde(rename = "sessionId", default)]
     session_id: Option<String>,
     timestamp: String,
@@ -263,7 +659,7 @@ struct RawRecord {
     cost_usd: Option<f64>,
     #[serde(rename = "requestId", default)]
     request_id: Option<String>,
-    #[serde(default)]
+    #[serde(rename = "isApiErrorMessage", alias = "is_api_error_message", default)]
     is_api_error_message: Option<bool>,
 }
 
@@ -276,117 +672,767 @@ struct RawMessage {
     model: Option<String>,
     #[serde(default)]
     id: Option<String>,
+    #[serde(default)]
+    content: Option<RawContent>,
+}
+
+/// Message content: typed blocks for assistant lines, plain text for most user lines.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawContent {
+    Blocks(Vec<RawContentBlock>),
+    Other(serde::de::IgnoredAny),
 }
 
 #[derive(Debug, Deserialize)]
+struct RawContentBlock {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ApiErrorKind {
+    ApiError,
+    /// HTTP 529 / `overloaded_error`
+    Overloaded,
+    /// Synthetic "usage limit reached" message
+    UsageLimit,
+}
+
+/// An API error or usage-limit message Claude Code wrote in place of a response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiError {
+    kind: ApiErrorKind,
+    message: String,
+}
+
+impl ApiError {
+    /// When the usage limit resets, if the message says; read in `tz` when it names no zone.
+    fn resets_at(&self, at: DateTime<Utc>, tz: &Tz) -> Option<DateTime<Utc>> {
+        if self.kind != ApiErrorKind::UsageLimit {
+            return None;
+        }
+        parse_reset_time(&self.message, at, tz)
+    }
+}
+
+/// A `tool_use` block: the tool an assistant turn called.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ToolUse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct RawUsage {
     input_tokens: Option<u64>,
     output_tokens: Option<u64>,
     cache_creation_input_tokens: Option<u64>,
     cache_read_input_tokens: Option<u64>,
+    #[serde(default)]
+    cache_creation: Option<RawCacheCreation>,
 }
 
-#[derive(Debug, Clone)]
+/// Cache writes split by TTL; absent in transcripts older than the 1-hour cache.
+#[derive(Debug, Deserialize)]
+struct RawCacheCreation {
+    ephemeral_5m_input_tokens: Option<u64>,
+    ephemeral_1h_input_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct UsageEvent {
     timestamp: DateTime<Utc>,
+    /// Report label: the working directory relative to the project root or home
+    /// directory; recomputed when loaded from the index
+    #[serde(skip)]
     project: String,
+    /// Encoded directory name under `projects/` holding the transcript
+    project_dir: String,
+    /// Working directory recorded on the transcript line
+    cwd: Option<String>,
+    /// Git branch checked out in `cwd` when the line was written
+    git_branch: Option<String>,
+    /// Written by a Task-tool subagent rather than the main thread
+    #[serde(default)]
+    is_sidechain: bool,
+    /// Claude Code version that wrote the line
+    #[serde(default)]
+    version: Option<String>,
     session_id: String,
     model: Option<String>,
     input_tokens: u64,
     output_tokens: u64,
+    /// Sum of both cache-write tiers
     cache_creation_tokens: u64,
+    cache_creation_5m_tokens: u64,
+    cache_creation_1h_tokens: u64,
     cache_read_tokens: u64,
-    cost_usd: f64,
+    /// Resolved from `logged_cost_usd` and pricing; recomputed when loaded from the index
+    #[serde(skip)]
+    cost_usd: Money,
+    #[serde(skip)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency, at the rate for the event's day
+    #[serde(skip)]
+    converted_cost: Option<Money>,
+    /// Billed at the model's long-context tier
+    #[serde(skip)]
+    long_context: bool,
+    /// Model had no pricing and no logged cost, so the event counted as free
+    #[serde(skip)]
+    unpriced: bool,
+    /// `costUSD` as written in the transcript, if any
+    logged_cost_usd: Option<f64>,
+    /// `message.id:requestId`, present only when both ids were logged
+    dedup_key: Option<String>,
+    /// Tools called by the assistant message, in call order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_uses: Vec<ToolUse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_error: Option<ApiError>,
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct ModelBreakdown {
-    model: String,
-    input_tokens: u64,
-    output_tokens: u64,
-    cache_creation_tokens: u64,
-    cache_read_tokens: u64,
-    total_tokens: u64,
-    cost_usd: f64,
+impl UsageEvent {
+    /// An error line Claude Code logged without any usage; it counts as an error, not a turn.
+    fn is_error_only(&self) -> bool {
+        self.api_error.is_some() && event_total_tokens(self) == 0
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct Row {
-    key: String,
-    input_tokens: u64,
-    output_tokens: u64,
-    cache_creation_tokens: u64,
-    cache_read_tokens: u64,
-    total_tokens: u64,
-    cost_usd: f64,
-    models: BTreeSet<String>,
-    projects: BTreeSet<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    model_breakdowns: Vec<ModelBreakdown>,
+/// Drops usage entries that were already seen, e.g. assistant messages copied
+/// into a new transcript when a session is resumed or forked.
+#[derive(Default)]
+struct Deduplicator {
+    seen: HashSet<String>,
+    removed: usize,
 }
 
-#[derive(Debug, Serialize)]
-struct Totals {
-    input_tokens: u64,
-    output_tokens: u64,
-    cache_creation_tokens: u64,
-    cache_read_tokens: u64,
-    total_tokens: u64,
-    cost_usd: f64,
+impl Deduplicator {
+    /// Streamed responses log one line per content block, each repeating the usage, so
+    /// the tool calls on dropped lines are folded into the line kept from the same batch.
+    fn retain_new(&mut self, events: Vec<UsageEvent>) -> Vec<UsageEvent> {
+        let mut kept: Vec<UsageEvent> = Vec::with_capacity(events.len());
+        let mut kept_at: HashMap<String, usize> = HashMap::new();
+        for ev in events {
+            match &ev.dedup_key {
+                Some(key) if !self.seen.insert(key.clone()) => {
+                    self.removed += 1;
+                    if let Some(&at) = kept_at.get(key) {
+                        let tool_uses = &mut kept[at].tool_uses;
+                        for tool in ev.tool_uses {
+                            if !tool_uses.contains(&tool) {
+                                tool_uses.push(tool);
+                            }
+                        }
+                    }
+                }
+                Some(key) => {
+                    kept_at.insert(key.clone(), kept.len());
+                    kept.push(ev);
+                }
+                None => kept.push(ev),
+            }
+        }
+        kept
+    }
+
+    fn removed(&self) -> usize {
+        self.removed
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct JsonReport {
-    kind: &'static str,
-    timezone: String,
-    locale: String,
-    since: Option<String>,
-    until: Option<String>,
-    rows: Vec<Row>,
-    totals: Totals,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    model_breakdowns: Vec<ModelBreakdown>,
+/// USD amount in integer micro-dollars, so sums are exact and every report's totals
+/// reconcile. Serialized as dollars; converted to floating point only for output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Money(i64);
+
+impl Money {
+    const ZERO: Money = Money(0);
+
+    fn from_micros(micros: f64) -> Self {
+        Money(micros.round() as i64)
+    }
+
+    fn from_usd(usd: f64) -> Self {
+        Money::from_micros(usd * 1_000_000.0)
+    }
+
+    fn usd(self) -> f64 {
+        self.0 as f64 / 1_000_000.0
+    }
+
+    fn abs(self) -> Self {
+        Money(self.0.abs())
+    }
+
+    /// Rounded to the nearest micro-dollar; for derived figures such as rates and projections.
+    fn scaled(self, factor: f64) -> Self {
+        Money::from_micros(self.0 as f64 * factor)
+    }
+
+    /// `self / other` as a fraction; `None` when `other` is zero.
+    fn ratio(self, other: Money) -> Option<f64> {
+        (other.0 != 0).then(|| self.0 as f64 / other.0 as f64)
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct SessionRow {
-    session_id: String,
-    project: String,
-    last_activity: String,
+impl Serialize for Money {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_f64(self.usd())
+    }
+}
+
+impl std::ops::Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl std::ops::Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl std::ops::AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+/// Adds an amount that only exists for some runs, like a `--currency` conversion.
+fn add_converted(total: &mut Option<Money>, amount: Option<Money>) {
+    if let Some(amount) = amount {
+        *total = Some(total.unwrap_or_default() + amount);
+    }
+}
+
+fn sidechain_mode() -> SidechainMode {
+    SIDECHAIN.get().copied().unwrap_or_default()
+}
+
+/// Tokens and cost of one side of a `--sidechain split`.
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+struct ThreadUsage {
     input_tokens: u64,
     output_tokens: u64,
     cache_creation_tokens: u64,
     cache_read_tokens: u64,
     total_tokens: u64,
-    cost_usd: f64,
-    models: BTreeSet<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    model_breakdowns: Vec<ModelBreakdown>,
+    cost_usd: Money,
 }
 
-#[derive(Debug, Serialize)]
-struct SessionReport {
-    kind: &'static str,
-    timezone: String,
+impl std::ops::AddAssign for ThreadUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// Main-thread versus subagent usage.
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+struct SidechainSplit {
+    main: ThreadUsage,
+    subagent: ThreadUsage,
+}
+
+impl SidechainSplit {
+    fn add(&mut self, ev: &UsageEvent) {
+        let side = if ev.is_sidechain {
+            &mut self.subagent
+        } else {
+            &mut self.main
+        };
+        *side += ThreadUsage {
+            input_tokens: ev.input_tokens,
+            output_tokens: ev.output_tokens,
+            cache_creation_tokens: ev.cache_creation_tokens,
+            cache_read_tokens: ev.cache_read_tokens,
+            total_tokens: event_total_tokens(ev),
+            cost_usd: ev.cost_usd,
+        };
+    }
+
+    /// Reported only with `--sidechain split`.
+    fn reported(self) -> Option<Self> {
+        (sidechain_mode() == SidechainMode::Split).then_some(self)
+    }
+}
+
+impl std::ops::AddAssign for SidechainSplit {
+    fn add_assign(&mut self, other: Self) {
+        self.main += other.main;
+        self.subagent += other.subagent;
+    }
+}
+
+fn add_split(total: &mut Option<SidechainSplit>, split: Option<SidechainSplit>) {
+    if let Some(split) = split {
+        *total.get_or_insert_default() += split;
+    }
+}
+
+impl std::iter::Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + m)
+    }
+}
+
+/// Cost split by token category. The parts sum to the matching `cost_usd` whenever
+/// the model has pricing; a logged `costUSD` for an unpriced model stays unsplit.
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq)]
+struct CostBreakdown {
+    input_cost: Money,
+    output_cost: Money,
+    cache_write_cost: Money,
+    cache_read_cost: Money,
+}
+
+impl CostBreakdown {
+    fn total(&self) -> Money {
+        self.input_cost + self.output_cost + self.cache_write_cost + self.cache_read_cost
+    }
+
+    /// Splits `total` in proportion to these parts. Rounding leftovers go to the
+    /// largest part so the split sums to `total` exactly.
+    fn apportion(self, total: Money) -> Self {
+        let Some(factor) = total.ratio(self.total()) else {
+            return CostBreakdown::default();
+        };
+        let mut parts = [
+            self.input_cost,
+            self.output_cost,
+            self.cache_write_cost,
+            self.cache_read_cost,
+        ]
+        .map(|part| part.scaled(factor));
+        let largest = (0..parts.len()).max_by_key(|&i| parts[i]).unwrap_or(0);
+        let assigned: Money = parts.iter().copied().sum();
+        parts[largest] += total - assigned;
+        let [input_cost, output_cost, cache_write_cost, cache_read_cost] = parts;
+        CostBreakdown {
+            input_cost,
+            output_cost,
+            cache_write_cost,
+            cache_read_cost,
+        }
+    }
+}
+
+impl std::ops::AddAssign for CostBreakdown {
+    fn add_assign(&mut self, other: Self) {
+        self.input_cost += other.input_cost;
+        self.output_cost += other.output_cost;
+        self.cache_write_cost += other.cache_write_cost;
+        self.cache_read_cost += other.cache_read_cost;
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ModelBreakdown {
+    model: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_creation_5m_tokens: u64,
+    cache_creation_1h_tokens: u64,
+    cache_read_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    #[serde(skip)]
+    unpriced_requests: u64,
+    #[serde(skip)]
+    unpriced_tokens: u64,
+}
+
+/// Usage of a model without pricing that counted as $0.00.
+#[derive(Debug, Serialize, Clone)]
+struct UnpricedModel {
+    model: String,
+    requests: u64,
+    total_tokens: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    key: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    /// Requests billed at long-context rates
+    long_context_requests: u64,
+    /// Main-thread and subagent figures with `--sidechain split`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sidechain: Option<SidechainSplit>,
+    models: BTreeSet<String>,
+    projects: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    model_breakdowns: Vec<ModelBreakdown>,
+}
+
+#[derive(Debug, Serialize)]
+struct Totals {
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    /// Requests billed at long-context rates
+    long_context_requests: u64,
+    /// Main-thread and subagent figures with `--sidechain split`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sidechain: Option<SidechainSplit>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonReport {
+    kind: &'static str,
+    timezone: String,
     locale: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    rows: Vec<Row>,
+    totals: Totals,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    model_breakdowns: Vec<ModelBreakdown>,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionRow {
+    session_id: String,
+    project: String,
+    last_activity: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    /// Requests billed at long-context rates
+    long_context_requests: u64,
+    /// Main-thread and subagent figures with `--sidechain split`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sidechain: Option<SidechainSplit>,
+    models: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    model_breakdowns: Vec<ModelBreakdown>,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionReport {
+    kind: &'static str,
+    timezone: String,
+    locale: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
     since: Option<String>,
     until: Option<String>,
     rows: Vec<SessionRow>,
     totals: Totals,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     model_breakdowns: Vec<ModelBreakdown>,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct BranchRow {
+    /// Git checkout the usage happened in, labelled like projects
+    repository: String,
+    /// Absent for lines written outside a git checkout or before Claude recorded branches
+    branch: Option<String>,
+    sessions: usize,
+    last_activity: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    models: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BranchReport {
+    kind: &'static str,
+    timezone: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    rows: Vec<BranchRow>,
+    totals: Totals,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct VersionRow {
+    /// Absent for lines written before Claude Code recorded its version
+    version: Option<String>,
+    first_seen: String,
+    last_seen: String,
+    /// Days with usage from this version
+    days: usize,
+    sessions: usize,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    /// Cache reads over all input tokens (fresh, cache writes and cache reads)
+    cache_hit_ratio: Option<f64>,
+    /// Change in `cache_hit_ratio` from the version seen before this one
+    cache_hit_ratio_change: Option<f64>,
+    models: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct VersionsReport {
+    kind: &'static str,
+    timezone: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    rows: Vec<VersionRow>,
+    totals: Totals,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+struct ErrorCounts {
+    api_errors: u64,
+    overloaded: u64,
+    usage_limits: u64,
+    total: u64,
+}
+
+impl ErrorCounts {
+    fn add(&mut self, kind: ApiErrorKind) {
+        match kind {
+            ApiErrorKind::ApiError => self.api_errors += 1,
+            ApiErrorKind::Overloaded => self.overloaded += 1,
+            ApiErrorKind::UsageLimit => self.usage_limits += 1,
+        }
+        self.total += 1;
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorEntry {
+    timestamp: String,
+    kind: ApiErrorKind,
+    session_id: String,
+    project: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resets_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorDay {
+    date: String,
+    #[serde(flatten)]
+    counts: ErrorCounts,
+}
+
+/// A usage block that saw errors, with the usage it managed before them.
+#[derive(Debug, Serialize)]
+struct ErrorBlock {
+    block_start: String,
+    block_end: String,
+    #[serde(flatten)]
+    counts: ErrorCounts,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_limit_hit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit_resets_at: Option<String>,
+    total_tokens: u64,
+    cost_usd: Money,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorsReport {
+    kind: &'static str,
+    timezone: String,
+    since: Option<String>,
+    until: Option<String>,
+    /// Oldest first
+    events: Vec<ErrorEntry>,
+    days: Vec<ErrorDay>,
+    blocks: Vec<ErrorBlock>,
+    totals: ErrorCounts,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+/// Invocations of a tool (or MCP server) and its share of the calling turns' usage.
+#[derive(Debug, Serialize)]
+struct ToolUsage {
+    invocations: u64,
+    /// Assistant turns that made at least one of the calls
+    turns: u64,
+    sessions: usize,
+    /// Each turn's tokens and cost are split evenly across the calls it made
+    total_tokens: u64,
+    cost_usd: Money,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    /// Invocations per day in the report timezone
+    by_day: BTreeMap<String, u64>,
+    by_project: BTreeMap<String, u64>,
+    by_session: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolRow {
+    tool: String,
+    /// Server of an `mcp__server__tool` tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mcp_server: Option<String>,
+    #[serde(flatten)]
+    usage: ToolUsage,
+}
+
+#[derive(Debug, Serialize)]
+struct McpServerRow {
+    server: String,
+    tools: BTreeSet<String>,
+    #[serde(flatten)]
+    usage: ToolUsage,
+}
+
+#[derive(Debug, Serialize, Default)]
+struct ToolTotals {
+    invocations: u64,
+    turns: u64,
+    total_tokens: u64,
+    cost_usd: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolsReport {
+    kind: &'static str,
+    timezone: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    rows: Vec<ToolRow>,
+    mcp_servers: Vec<McpServerRow>,
+    /// Turns that called tools; matches the sum of `rows`
+    totals: ToolTotals,
+    /// Turns that called no tool
+    without_tools: ToolTotals,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
 }
 
 #[derive(Debug, Serialize)]
 struct BlockRow {
     block_start: String,
     block_end: String,
+    /// Idle period between two blocks rather than a usage window
+    is_gap: bool,
+    /// Window is still open and saw activity within the last block length
+    is_active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_activity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    elapsed_minutes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining_minutes: Option<i64>,
     input_tokens: u64,
     output_tokens: u64,
     cache_creation_tokens: u64,
     cache_read_tokens: u64,
     total_tokens: u64,
-    cost_usd: f64,
+    cost_usd: Money,
+    #[serde(flatten)]
+    costs: CostBreakdown,
+    /// `cost_usd` in the `--currency` currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_cost: Option<Money>,
+    /// Requests billed at long-context rates
+    long_context_requests: u64,
+    /// Main-thread and subagent figures with `--sidechain split`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sidechain: Option<SidechainSplit>,
+    /// API errors and usage-limit hits inside the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<ErrorCounts>,
+    /// Reset time announced by the block's last usage-limit message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit_resets_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     percent_of_limit: Option<f64>,
     models: BTreeSet<String>,
@@ -400,12 +1446,32 @@ struct BlocksReport {
     kind: &'static str,
     timezone: String,
     locale: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
     recent_days: u32,
     token_limit: Option<u64>,
     rows: Vec<BlockRow>,
     totals: Totals,
+    /// Burn rate and end-of-block projection for the active block, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    burn_rate: Option<BurnRate>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     model_breakdowns: Vec<ModelBreakdown>,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct BurnRate {
+    window_minutes: i64,
+    tokens_per_minute: f64,
+    cost_per_hour: Money,
+    projected_total_tokens: u64,
+    projected_cost_usd: Money,
+    /// When `token_limit` is reached at the current rate, if before the block ends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit_reached_at: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -413,48 +1479,377 @@ struct StatuslineReport {
     kind: &'static str,
     timezone: String,
     locale: String,
+    /// `--currency` code for the `converted_cost` fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
     last_date: Option<String>,
     totals: Totals,
+    /// Present when invoked from Claude Code's statusLine hook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<StatuslineSession>,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditReport {
+    kind: &'static str,
+    timezone: String,
+    since: Option<String>,
+    until: Option<String>,
+    tolerance_percent: f64,
+    days: Vec<AuditRow>,
+    models: Vec<AuditRow>,
+    totals: AuditRow,
+    /// Events without a logged `costUSD`; not part of the deltas
+    missing_cost_usd: MissingCost,
+    /// Models whose delta exceeds the tolerance
+    over_tolerance: Vec<String>,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+/// Logged versus calculated cost over the events that have a logged `costUSD`.
+#[derive(Debug, Serialize, Default)]
+struct AuditRow {
+    key: String,
+    events: u64,
+    logged_cost_usd: Money,
+    calculated_cost_usd: Money,
+    delta_usd: Money,
+    /// Delta relative to the calculated cost; absent when that is zero
+    delta_percent: Option<f64>,
+    missing_cost_usd_events: u64,
+}
+
+#[derive(Debug, Serialize, Default)]
+struct MissingCost {
+    events: u64,
+    calculated_cost_usd: Money,
+    /// The earliest `MISSING_COST_SAMPLE_SIZE` of them
+    sample: Vec<MissingCostEvent>,
+}
+
+#[derive(Debug, Serialize)]
+struct MissingCostEvent {
+    timestamp: String,
+    session_id: String,
+    project: String,
+    model: Option<String>,
+    calculated_cost_usd: Money,
+}
+
+#[derive(Debug, Serialize)]
+struct PlanReport {
+    kind: &'static str,
+    timezone: String,
+    plan: PlanTier,
+    monthly_fee_usd: Money,
+    renewal_day: u32,
+    cycles: Vec<PlanCycle>,
+    totals: PlanCycle,
+    unpriced_models: Vec<UnpricedModel>,
+    duplicates_removed: usize,
+}
+
+/// API-equivalent value of one billing cycle against the plan fee.
+#[derive(Debug, Serialize, Default)]
+struct PlanCycle {
+    cycle_start: String,
+    cycle_end: String,
+    /// Cycle contains today
+    is_current: bool,
+    total_tokens: u64,
+    api_cost_usd: Money,
+    plan_fee_usd: Money,
+    /// API-equivalent cost divided by the fee; above 1 the plan pays off
+    value_multiplier: f64,
+    /// Savings over paying API rates; negative when the fee exceeds the usage
+    net_usd: Money,
+    /// First day the cycle's cumulative API cost reached the fee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    break_even_date: Option<String>,
+}
+
+impl PlanCycle {
+    fn finish(mut self) -> Self {
+        self.net_usd = self.api_cost_usd - self.plan_fee_usd;
+        self.value_multiplier = self.api_cost_usd.ratio(self.plan_fee_usd).unwrap_or(0.0);
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StatuslineSession {
+    session_id: Option<String>,
+    model: Option<String>,
+    cwd: Option<String>,
+    session_cost_usd: Money,
+    today_cost_usd: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_cost_usd: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_remaining_minutes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    burn_rate: Option<BurnRate>,
+}
+
+/// Payload Claude Code pipes to the `statusLine` command.
+#[derive(Debug, Deserialize)]
+struct StatuslineHook {
+    #[serde(default)]
+    session_id: Option<String>,
+    #[serde(default)]
+    transcript_path: Option<PathBuf>,
+    #[serde(default)]
+    model: Option<StatuslineHookModel>,
+    #[serde(default)]
+    cwd: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize)]
+struct StatuslineHookModel {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    display_name: Option<String>,
+}
+
+/// Parsed transcripts written to since `since`, so a statusline refresh only has
+/// to read what was appended to the active session's transcript.
+#[derive(Serialize, Deserialize)]
+struct StatuslineCache {
+    version: u32,
+    /// Data directories the transcripts were found in
+    data_dirs: Vec<PathBuf>,
+    since: DateTime<Utc>,
+    /// When the data directories were last scanned for other active transcripts
+    refreshed_at: DateTime<Utc>,
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 struct Pricing {
+    /// First day (UTC) these rates apply; open-ended when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    effective_from: Option<NaiveDate>,
+    /// Last day (UTC) these rates apply; open-ended when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    effective_until: Option<NaiveDate>,
     input_per_million: f64,
     output_per_million: f64,
+    /// 5-minute cache writes
     #[serde(default)]
     cache_create_per_million: f64,
+    /// 1-hour cache writes; twice the input rate when the source omits it
+    #[serde(default)]
+    cache_create_1h_per_million: Option<f64>,
     #[serde(default)]
     cache_read_per_million: f64,
+    /// Long-context rates for requests whose prompt exceeds 200k tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    input_above_200k_per_million: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_above_200k_per_million: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_create_above_200k_per_million: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_create_1h_above_200k_per_million: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_read_above_200k_per_million: Option<f64>,
+}
+
+impl Pricing {
+    fn in_effect(&self, date: NaiveDate) -> bool {
+        self.effective_from.is_none_or(|from| from <= date)
+            && self.effective_until.is_none_or(|until| date <= until)
+    }
+
+    fn same_rates(&self, other: &Pricing) -> bool {
+        let undated = |p: &Pricing| Pricing {
+            effective_from: None,
+            effective_until: None,
+            ..p.clone()
+        };
+        undated(self) == undated(other)
+    }
+
+    fn cache_create_1h_rate(&self) -> f64 {
+        self.cache_create_1h_per_million
+            .unwrap_or(self.input_per_million * 2.0)
+    }
+
+    /// Flat rates of the long-context tier, if the model has one. Tier rates
+    /// missing from the source fall back to the base rates.
+    fn long_context_tier(&self) -> Option<Pricing> {
+        let input = self.input_above_200k_per_million?;
+        Some(Pricing {
+            input_per_million: input,
+            output_per_million: self
+                .output_above_200k_per_million
+                .unwrap_or(self.output_per_million),
+            cache_create_per_million: self
+                .cache_create_above_200k_per_million
+                .unwrap_or(self.cache_create_per_million),
+            cache_create_1h_per_million: self.cache_create_1h_above_200k_per_million,
+            cache_read_per_million: self
+                .cache_read_above_200k_per_million
+                .unwrap_or(self.cache_read_per_million),
+            ..Pricing::default()
+        })
+    }
 }
 
+/// Rates of one pricing key over time, oldest first.
+type PricingHistory = Vec<Pricing>;
+
 #[derive(Clone)]
 struct PricingIndex {
-    entries: Vec<(String, Pricing)>,
+    /// Rates of the current table
+    entries: Vec<(String, PricingHistory)>,
+    /// Day the current table was fetched; earlier usage may need archived rates
+    current_date: NaiveDate,
+    /// Archived and current rates over time, loaded on first use
+    dated_entries: OnceCell<Vec<(String, PricingHistory)>>,
+    /// Config file entries, matched before `entries` and applied on top of them
+    overrides: Vec<(String, Vec<ConfigRates>)>,
+    aliases: HashMap<String, String>,
+    /// Description of where `entries` came from
+    source: String,
+}
+
+/// Result of a pricing lookup, printed by `pricing show`.
+#[derive(Serialize)]
+struct PricingMatch<'a> {
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alias_of: Option<&'a str>,
+    matched_key: &'a str,
+    /// `config` for config file entries, `pricing history` for superseded
+    /// archived rates, otherwise the pricing data source
+    origin: &'a str,
+    pricing: Pricing,
 }
 
 impl PricingIndex {
-    fn from_map(map: HashMap<String, Pricing>) -> Self {
-        let mut entries: Vec<_> = map.into_iter().collect();
-        // Longest prefix first for matching
-        entries.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
-        PricingIndex { entries }
+    fn find(&self, model: &str, date: NaiveDate) -> Option<Pricing> {
+        self.lookup(model, date).map(|m| m.pricing)
     }
 
-    fn find(&self, model: &str) -> Option<&Pricing> {
-        self.entries
-            .iter()
-            .find(|(prefix, _)| model.starts_with(prefix))
-            .map(|(_, p)| p)
+    /// Config entries only match inside their effective window; pricing data
+    /// histories are contiguous, so the data always has a rate for a known key.
+    fn lookup(&self, model: &str, date: NaiveDate) -> Option<PricingMatch<'_>> {
+        let alias_of = self.aliases.get(model).map(String::as_str);
+        let target = alias_of.unwrap_or(model);
+        let data = self.find_data(target, date);
+        let (origin, key, pricing) = find_config_rates(&self.overrides, target, date)
+            .map(|(key, rates)| ("config", key, rates.over(data.map(|(_, p)| p))))
+            .or_else(|| {
+                data.map(|(key, pricing)| {
+                    // Only superseded rates have an end date
+                    let origin = if pricing.effective_until.is_some() {
+                        "pricing history"
+                    } else {
+                        self.source.as_str()
+                    };
+                    (origin, key, pricing.clone())
+                })
+            })?;
+        Some(PricingMatch {
+            model: model.to_string(),
+            alias_of,
+            matched_key: key,
+            origin,
+            pricing,
+        })
     }
+
+    /// Pricing data rates for `model` on `date`. Archived snapshots are read only for
+    /// usage before the current table, or for models it no longer lists.
+    fn find_data(&self, model: &str, date: NaiveDate) -> Option<(&str, &Pricing)> {
+        if date >= self.current_date
+            && let Some(found) = find_in_effect(&self.entries, model, date)
+        {
+            return Some(found);
+        }
+        let dated = self.dated_entries.get_or_init(|| {
+            let current = self
+                .entries
+                .iter()
+                .filter_map(|(key, history)| Some((key.clone(), history.last()?.clone())))
+                .collect();
+            let timeline = load_pricing_history()
+                .into_iter()
+                .filter_map(|archived| Some((archived.fetched_at?.date_naive(), archived.models)))
+                .filter(|(archived_on, _)| *archived_on < self.current_date)
+                .chain(std::iter::once((self.current_date, current)));
+            sorted_by_prefix(merge_pricing_timeline(timeline))
+        });
+        find_in_effect(dated, model, date)
+    }
+
+    /// Current Claude entries by name, config entries taking precedence.
+    fn claude_models(&self) -> BTreeMap<&str, (Pricing, &str)> {
+        let mut models = BTreeMap::new();
+        for (key, history) in &self.entries {
+            if key.starts_with("claude")
+                && let Some(current) = history.last()
+            {
+                models.insert(key.as_str(), (current.clone(), self.source.as_str()));
+            }
+        }
+        let today = Utc::now().date_naive();
+        for (key, history) in &self.overrides {
+            if let Some(current) = history.last() {
+                let data = self.find_data(key, today).map(|(_, p)| p);
+                models.insert(key.as_str(), (current.over(data), "config"));
+            }
+        }
+        models
+    }
+}
+
+fn sorted_by_prefix<T>(map: HashMap<String, T>) -> Vec<(String, T)> {
+    let mut entries: Vec<_> = map.into_iter().collect();
+    // Longest prefix first for matching
+    entries.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    entries
+}
+
+fn find_in_effect<'a>(
+    entries: &'a [(String, PricingHistory)],
+    model: &str,
+    date: NaiveDate,
+) -> Option<(&'a str, &'a Pricing)> {
+    let (key, history) = entries.iter().find(|(prefix, _)| model.starts_with(prefix))?;
+    let pricing = history.iter().find(|p| p.in_effect(date))?;
+    Some((key.as_str(), pricing))
+}
+
+fn find_config_rates<'a>(
+    entries: &'a [(String, Vec<ConfigRates>)],
+    model: &str,
+    date: NaiveDate,
+) -> Option<(&'a str, &'a ConfigRates)> {
+    let (key, history) = entries.iter().find(|(prefix, _)| model.starts_with(prefix))?;
+    let rates = history.iter().find(|r| r.in_effect(date))?;
+    Some((key.as_str(), rates))
 }
 
 static PRICING_INDEX: OnceCell<PricingIndex> = OnceCell::new();
 static COST_MODE: OnceCell<CostMode> = OnceCell::new();
+static CURRENCY: OnceCell<Currency> = OnceCell::new();
+static PROJECT_ROOT: OnceCell<PathBuf> = OnceCell::new();
+static SIDECHAIN: OnceCell<SidechainMode> = OnceCell::new();
+static DECODED_PROJECT_DIRS: OnceCell<Mutex<HashMap<String, Option<PathBuf>>>> = OnceCell::new();
 static CONFIG_SCHEMA: OnceCell<JSONSchema> = OnceCell::new();
 static CONFIG_SCHEMA_JSON: OnceCell<Value> = OnceCell::new();
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(Command::Cache(args)) = &cli.command {
+        return run_cache_command(args);
+    }
     let file_cfg = load_config(cli.config.as_ref())?;
 
     let global_defaults = file_cfg.defaults.as_ref();
@@ -482,11 +1877,87 @@ fn main() -> Result<()> {
         false,
     );
     let cost_mode = resolve_cost_mode(cli.cost_mode, global_defaults.and_then(|d| d.cost_mode));
-    let pricing_index = build_pricing_index(load_pricing(offline_pricing)?);
+    if let Some(Command::Pricing(args)) = &cli.command {
+        let json = resolve_bool(cli.json, None, global_defaults.and_then(|d| d.json), false);
+        return run_pricing_command(args, offline_pricing, file_cfg.pricing.as_ref(), json);
+    }
+    let pricing_index = build_pricing_index(load_pricing(offline_pricing)?, file_cfg.pricing.as_ref());
     let _ = PRICING_INDEX.set(pricing_index);
     let _ = COST_MODE.set(cost_mode);
+    if let Some(code) = resolve_string(
+        cli.currency.as_ref(),
+        None,
+        global_defaults.and_then(|d| d.currency.as_ref()),
+    ) {
+        let rates_path = cli
+            .exchange_rates
+            .clone()
+            .or_else(|| global_defaults.and_then(|d| d.exchange_rates.clone()));
+        let _ = CURRENCY.set(load_currency(&code, rates_path.as_deref(), tz)?);
+    }
+    let no_cache = resolve_bool(
+        cli.no_cache,
+        None,
+        global_defaults.and_then(|d| d.no_cache),
+        false,
+    );
+    let strict_pricing = resolve_bool(
+        cli.strict_pricing,
+        None,
+        global_defaults.and_then(|d| d.strict_pricing),
+        false,
+    );
+
+    let _ = SIDECHAIN.set(
+        cli.sidechain
+            .or(global_defaults.and_then(|d| d.sidechain))
+            .unwrap_or_default(),
+    );
+    if let Some(root) = cli
+        .project_root
+        .clone()
+        .or_else(|| global_defaults.and_then(|d| d.project_root.clone()))
+    {
+        let _ = PROJECT_ROOT.set(expand_tilde(&root));
+    }
+
+    let data_dirs = resolve_data_dirs(&cli.data_dirs)?;
+
+    if let Some(Command::Statusline(args)) = &cli.command
+        && let Some(hook) = read_statusline_hook()?
+    {
+        let statusline_cfg = file_cfg
+            .commands
+            .as_ref()
+            .and_then(|c| c.statusline.as_ref());
+        let cmd_cfg = statusline_cfg.map(|c| &c.common);
+        let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+        let template = resolve_statusline_template(args, statusline_cfg)?;
+        let token_limit = resolve_u64(
+            None,
+            cmd_cfg.and_then(|c| c.token_limit),
+            global_defaults.and_then(|d| d.token_limit),
+            500_000,
+        );
+        let session_length_hours = resolve_u32(
+            None,
+            cmd_cfg.and_then(|c| c.session_length_hours),
+            global_defaults.and_then(|d| d.session_length_hours),
+            DEFAULT_BLOCK_HOURS,
+        );
+        let report = build_hook_statusline_report(
+            &hook,
+            &tz,
+            &data_dirs,
+            !no_cache,
+            token_limit,
+            session_length_hours,
+        )?;
+        ensure_priced(&report.unpriced_models, strict_pricing)?;
+        output_statusline(report, opts.json, template.as_deref());
+        return Ok(());
+    }
 
-    let data_dirs = resolve_data_dirs(&cli.data_dirs)?;
     let files = collect_jsonl_files(&data_dirs);
 
     if files.is_empty() {
@@ -497,7 +1968,13 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let events = load_events(&files)?;
+    let index_path = if no_cache {
+        None
+    } else {
+        event_index_path()
+    };
+    let mut dedup = Deduplicator::default();
+    let events = load_events(&files, index_path.as_deref(), &mut dedup)?;
     if events.is_empty() {
         println!("No usage entries parsed from JSONL files.");
         return Ok(());
@@ -524,7 +2001,7 @@ fn main() -> Result<()> {
                 global_defaults.and_then(|d| d.instances),
                 false,
             );
-            let report = build_daily_report(
+            let mut report = build_daily_report(
                 &events,
                 &tz,
                 &locale,
@@ -533,6 +2010,8 @@ fn main() -> Result<()> {
                 instances,
                 opts.breakdown,
             )?;
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
             output_rows(
                 report,
                 opts.json,
@@ -562,7 +2041,7 @@ fn main() -> Result<()> {
                 cmd_cfg.and_then(|c| c.start_of_week),
                 global_defaults.and_then(|d| d.start_of_week),
             );
-            let report = build_weekly_report(
+            let mut report = build_weekly_report(
                 &events,
                 &tz,
                 &locale,
@@ -572,6 +2051,8 @@ fn main() -> Result<()> {
                 start_of_week,
                 opts.breakdown,
             )?;
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
             output_rows(
                 report,
                 opts.json,
@@ -584,27 +2065,35 @@ fn main() -> Result<()> {
         Command::Monthly(args) => {
             let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.monthly.as_ref());
             let opts = resolve_common(&cli, cmd_cfg, global_defaults);
-            let range = resolve_range(&args, cmd_cfg, global_defaults)?;
+            let range = resolve_range(&args.range, cmd_cfg, global_defaults)?;
             let order = resolve_order(
-                args.order,
+                args.range.order,
                 cmd_cfg.and_then(|c| c.order),
                 global_defaults.and_then(|d| d.order),
             );
             let instances = resolve_bool(
-                args.instances,
+                args.range.instances,
                 cmd_cfg.and_then(|c| c.instances),
                 global_defaults.and_then(|d| d.instances),
                 false,
             );
-            let report = build_monthly_report(
+            let cycle_start_day = resolve_cycle_start_day(
+                args.cycle_start_day,
+                cmd_cfg.and_then(|c| c.cycle_start_day),
+                global_defaults.and_then(|d| d.cycle_start_day),
+            )?;
+            let mut report = build_monthly_report(
                 &events,
                 &tz,
                 &locale,
                 &range,
                 order,
                 instances,
+                cycle_start_day,
                 opts.breakdown,
             )?;
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
             output_rows(
                 report,
                 opts.json,
@@ -618,7 +2107,9 @@ fn main() -> Result<()> {
             let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.sessions.as_ref());
             let opts = resolve_common(&cli, cmd_cfg, global_defaults);
             let range = resolve_range(&args, cmd_cfg, global_defaults)?;
-            let report = build_session_report(&events, &tz, &locale, &range, opts.breakdown)?;
+            let mut report = build_session_report(&events, &tz, &locale, &range, opts.breakdown)?;
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
             output_sessions(report, opts.json, &locale);
         }
         Command::Blocks(args) => {
@@ -663,8 +2154,12 @@ fn main() -> Result<()> {
             );
 
             if live {
-                let mut live_source =
-                    LiveEventSource::from_existing(data_dirs.clone(), &files, events.clone())?;
+                let mut live_source = LiveEventSource::from_existing(
+                    data_dirs.clone(),
+                    &files,
+                    events.clone(),
+                    dedup,
+                )?;
                 let (_watcher, rx) = watch_data_dirs(&data_dirs)?;
                 if use_tui {
                     run_blocks_live_tui(
@@ -676,6 +2171,7 @@ fn main() -> Result<()> {
                         recent_days,
                         session_length_hours,
                         opts.breakdown,
+                        strict_pricing,
                         refresh_seconds,
                         rx,
                     )?;
@@ -689,12 +2185,13 @@ fn main() -> Result<()> {
                         recent_days,
                         session_length_hours,
                         opts,
+                        strict_pricing,
                         refresh_seconds,
                         rx,
                     )?;
                 }
             } else {
-                let report = build_blocks_report(
+                let mut report = build_blocks_report(
                     &events,
                     &tz,
                     &locale,
@@ -704,24 +2201,150 @@ fn main() -> Result<()> {
                     session_length_hours,
                     opts.breakdown,
                 )?;
+                report.duplicates_removed = dedup.removed();
+                ensure_priced(&report.unpriced_models, strict_pricing)?;
                 output_blocks(report, opts.json, opts.compact, opts.breakdown, &locale);
             }
         }
         Command::Statusline(args) => {
-            let cmd_cfg = file_cfg
+            let statusline_cfg = file_cfg
                 .commands
                 .as_ref()
                 .and_then(|c| c.statusline.as_ref());
+            let cmd_cfg = statusline_cfg.map(|c| &c.common);
+            let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+            let range = resolve_range(&args.range, cmd_cfg, global_defaults)?;
+            let template = resolve_statusline_template(&args, statusline_cfg)?;
+            let mut report = build_statusline_report(&events, &tz, &range)?;
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_statusline(report, opts.json, template.as_deref());
+        }
+        Command::Audit(args) => {
+            let audit_cfg = file_cfg.commands.as_ref().and_then(|c| c.audit.as_ref());
+            let cmd_cfg = audit_cfg.map(|c| &c.common);
+            let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+            let range = resolve_range(&args.range, cmd_cfg, global_defaults)?;
+            let tolerance = args
+                .tolerance
+                .or(audit_cfg.and_then(|c| c.tolerance))
+                .unwrap_or(DEFAULT_AUDIT_TOLERANCE_PERCENT);
+            let mut report = build_audit_report(&events, &tz, &range, tolerance);
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_audit(report, opts.json, &locale);
+        }
+        Command::Branches(args) => {
+            let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.branches.as_ref());
+            let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+            let range = resolve_range(&args, cmd_cfg, global_defaults)?;
+            let order = resolve_order(
+                args.order,
+                cmd_cfg.and_then(|c| c.order),
+                global_defaults.and_then(|d| d.order),
+            );
+            let mut report = build_branches_report(&events, &tz, &range, order);
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_branches(report, opts.json, opts.compact, &locale);
+        }
+        Command::Tools(args) => {
+            let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.tools.as_ref());
+            let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+            let range = resolve_range(&args, cmd_cfg, global_defaults)?;
+            let order = resolve_order(
+                args.order,
+                cmd_cfg.and_then(|c| c.order),
+                global_defaults.and_then(|d| d.order),
+            );
+            let mut report = build_tools_report(&events, &tz, &range, order);
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_tools(report, opts.json, &locale);
+        }
+        Command::Errors(args) => {
+            let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.errors.as_ref());
+            let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+            let range = resolve_range(&args.range, cmd_cfg, global_defaults)?;
+            let session_length_hours = resolve_u32(
+                args.session_length_hours,
+                cmd_cfg.and_then(|c| c.session_length_hours),
+                global_defaults.and_then(|d| d.session_length_hours),
+                DEFAULT_BLOCK_HOURS,
+            );
+            let mut report = build_errors_report(&events, &tz, &range, session_length_hours);
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_errors(report, opts.json, &locale);
+        }
+        Command::Versions(args) => {
+            let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.versions.as_ref());
             let opts = resolve_common(&cli, cmd_cfg, global_defaults);
             let range = resolve_range(&args, cmd_cfg, global_defaults)?;
-            let report = build_statusline_report(&events, &tz, &range)?;
-            output_statusline(report, opts.json);
+            let order = resolve_order(
+                args.order,
+                cmd_cfg.and_then(|c| c.order),
+                global_defaults.and_then(|d| d.order),
+            );
+            let mut report = build_versions_report(&events, &tz, &range, order);
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_versions(report, opts.json, opts.compact, &locale);
+        }
+        Command::Plan(args) => {
+            let cmd_cfg = file_cfg.commands.as_ref().and_then(|c| c.plan.as_ref());
+            let opts = resolve_common(&cli, cmd_cfg, global_defaults);
+            let range = resolve_range(&args.range, cmd_cfg, global_defaults)?;
+            let plan_cfg = file_cfg.plan.as_ref();
+            let tier = args
+                .plan
+                .or(plan_cfg.and_then(|p| p.name))
+                .ok_or_else(|| {
+                    anyhow!("No plan configured; pass --plan or set `plan.name` in the config")
+                })?;
+            let monthly_price = args
+                .monthly_price
+                .or(plan_cfg.and_then(|p| p.monthly_price))
+                .or(tier.monthly_price())
+                .ok_or_else(|| {
+                    anyhow!("The custom plan needs --monthly-price or `plan.monthly_price`")
+                })?;
+            let renewal_day = resolve_cycle_start_day(
+                args.renewal_day.or(plan_cfg.and_then(|p| p.renewal_day)),
+                cmd_cfg.and_then(|c| c.cycle_start_day),
+                global_defaults.and_then(|d| d.cycle_start_day),
+            )?;
+            let mut report = build_plan_report(
+                &events,
+                &tz,
+                &range,
+                tier,
+                Money::from_usd(monthly_price),
+                renewal_day,
+            );
+            report.duplicates_removed = dedup.removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            output_plan(report, opts.json, &locale);
+        }
+        Command::Cache(_) | Command::Pricing(_) => {
+            unreachable!("cache and pricing commands run before loading events")
         }
     }
 
     Ok(())
 }
 
+fn ensure_priced(unpriced: &[UnpricedModel], strict: bool) -> Result<()> {
+    if strict && !unpriced.is_empty() {
+        let names: Vec<&str> = unpriced.iter().map(|m| m.model.as_str()).collect();
+        anyhow::bail!(
+            "no pricing for {} (--strict-pricing); add them under `pricing.models` in the config",
+            names.join(", ")
+        );
+    }
+    Ok(())
+}
+
 #[derive(Clone, Copy)]
 struct CommonOptions {
     json: bool,
@@ -761,6 +2384,25 @@ struct RangeFilter {
     since: Option<NaiveDate>,
     until: Option<NaiveDate>,
     project: Option<String>,
+    branch: Option<String>,
+}
+
+impl RangeFilter {
+    /// Usage events passing the filters. Error lines that carry no usage only
+    /// show up in the errors report and the blocks overlay.
+    fn includes(&self, ev: &UsageEvent) -> bool {
+        !ev.is_error_only() && self.matches(ev)
+    }
+
+    /// Project, branch and sidechain filters; dates are checked in the report's timezone.
+    fn matches(&self, ev: &UsageEvent) -> bool {
+        sidechain_mode().includes(ev)
+            && self.project.as_deref().is_none_or(|p| project_matches(ev, p))
+            && self
+                .branch
+                .as_deref()
+                .is_none_or(|b| ev.git_branch.as_deref() == Some(b))
+    }
 }
 
 fn resolve_range(
@@ -787,9 +2429,23 @@ fn resolve_range(
             cmd.and_then(|c| c.project.as_ref()),
             defaults.and_then(|d| d.project.as_ref()),
         ),
+        branch: resolve_string(
+            args.branch.as_ref(),
+            cmd.and_then(|c| c.branch.as_ref()),
+            defaults.and_then(|d| d.branch.as_ref()),
+        ),
     })
 }
 
+fn resolve_statusline_template(
+    args: &StatuslineArgs,
+    cmd: Option<&StatuslineConfig>,
+) -> Result<Option<Vec<TemplateSegment>>> {
+    resolve_string(args.format.as_ref(), cmd.and_then(|c| c.format.as_ref()), None)
+        .map(|raw| parse_statusline_template(&raw))
+        .transpose()
+}
+
 fn resolve_order(cli: Option<Order>, cmd: Option<Order>, defaults: Option<Order>) -> Order {
     cli.or(cmd).or(defaults).unwrap_or(Order::Desc)
 }
@@ -802,6 +2458,18 @@ fn resolve_week_start(
     cli.or(cmd).or(defaults).unwrap_or(WeekStart::Monday)
 }
 
+fn resolve_cycle_start_day(
+    cli: Option<u32>,
+    cmd: Option<u32>,
+    defaults: Option<u32>,
+) -> Result<u32> {
+    let day = cli.or(cmd).or(defaults).unwrap_or(1);
+    if !(1..=31).contains(&day) {
+        anyhow::bail!("Cycle start day must be between 1 and 31, got {day}");
+    }
+    Ok(day)
+}
+
 fn resolve_cost_mode(cli: Option<CostMode>, defaults: Option<CostMode>) -> CostMode {
     cli.or(defaults).unwrap_or(CostMode::Auto)
 }
@@ -900,6 +2568,45 @@ fn resolve_locale(raw: Option<String>) -> Locale {
     }
 }
 
+fn exchange_rates_path() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".config/ccusage/exchange-rates.json"))
+}
+
+/// Reads the rates for `code` from the exchange-rate file. USD needs no entry.
+fn load_currency(code: &str, path: Option<&Path>, tz: Tz) -> Result<Currency> {
+    let code = code.to_uppercase();
+    let path = path.map(expand_tilde).or_else(exchange_rates_path);
+    let file_rates = match &path {
+        Some(path) if path.exists() => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("reading exchange rates {}", path.display()))?;
+            serde_json::from_str::<HashMap<String, ExchangeRate>>(&text)
+                .with_context(|| format!("parsing exchange rates {}", path.display()))?
+        }
+        _ => HashMap::new(),
+    };
+    let rate = file_rates
+        .into_iter()
+        .find_map(|(key, rate)| (key.to_uppercase() == code).then_some(rate));
+    let mut rates = match rate {
+        Some(ExchangeRate::Fixed(rate)) => vec![(NaiveDate::MIN, rate)],
+        Some(ExchangeRate::Dated(entries)) => entries
+            .into_iter()
+            .map(|entry| (entry.effective_from, entry.rate))
+            .collect(),
+        None if code == "USD" => vec![(NaiveDate::MIN, 1.0)],
+        None => anyhow::bail!(
+            "No exchange rate for {code} in {}",
+            path.map_or_else(|| "the exchange-rate file".into(), |p| p.display().to_string())
+        ),
+    };
+    if rates.is_empty() || rates.iter().any(|(_, rate)| !(rate.is_finite() && *rate > 0.0)) {
+        anyhow::bail!("Exchange rates for {code} must be positive numbers");
+    }
+    rates.sort_by_key(|(from, _)| *from);
+    Ok(Currency { code, rates, tz })
+}
+
 fn parse_timezone(raw: Option<String>) -> Result<Tz> {
     let tz_str = raw.unwrap_or_else(|| "UTC".to_string());
     tz_str.parse::<Tz>().map_err(|e| {
@@ -943,12 +2650,11 @@ fn resolve_data_dirs(cli_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
 }
 
 fn expand_tilde(path: &Path) -> PathBuf {
-    if let Some(path_str) = path.to_str() {
-        if let Some(rest) = path_str.strip_prefix("~/") {
-            if let Some(home) = home_dir() {
-                return home.join(rest);
-            }
-        }
+    if let Some(path_str) = path.to_str()
+        && let Some(rest) = path_str.strip_prefix("~/")
+        && let Some(home) = home_dir()
+    {
+        return home.join(rest);
     }
     path.to_path_buf()
 }
@@ -957,206 +2663,747 @@ fn home_dir() -> Option<PathBuf> {
     std::env::var_os("HOME").map(PathBuf::from)
 }
 
-fn load_pricing(offline: bool) -> Result<HashMap<String, Pricing>> {
+/// Where the pricing table came from, as reported by `pricing show`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PricingSnapshot {
+    /// LiteLLM URL, imported file path, or `bundled`
+    source: String,
+    /// Imported files stay in use until the next update instead of expiring
+    #[serde(default)]
+    imported: bool,
+    fetched_at: Option<DateTime<Utc>>,
+    models: HashMap<String, Pricing>,
+}
+
+impl PricingSnapshot {
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.imported
+            || self
+                .fetched_at
+                .is_some_and(|at| now - at < chrono::Duration::hours(PRICING_CACHE_TTL_HOURS))
+    }
+
+    fn describe(&self) -> String {
+        match (self.imported, self.fetched_at) {
+            (true, Some(at)) => format!("imported from {} on {}", self.source, at.format("%Y-%m-%d %H:%M UTC")),
+            (false, Some(at)) => format!("{} (fetched {})", self.source, at.format("%Y-%m-%d %H:%M UTC")),
+            (_, None) => self.source.clone(),
+        }
+    }
+}
+
+fn load_pricing(offline: bool) -> Result<PricingSnapshot> {
     if offline {
         return load_bundled_pricing();
     }
-    if let Some(cache_path) = pricing_cache_path() {
-        if let Ok(map) = load_pricing_file(&cache_path) {
-            return Ok(map);
+    let stale = match pricing_cache_path().and_then(|path| load_pricing_file(&path).ok()) {
+        Some(snapshot) if snapshot.is_fresh(Utc::now()) => return Ok(snapshot),
+        other => other,
+    };
+    match fetch_remote_pricing() {
+        Ok(snapshot) => {
+            let _ = store_fetched_pricing(&snapshot);
+            Ok(snapshot)
+        }
+        Err(err) => {
+            eprintln!("Failed to fetch pricing remotely ({err}); falling back to cached or bundled pricing");
+            match stale {
+                Some(snapshot) => Ok(snapshot),
+                None => load_bundled_pricing(),
+            }
         }
     }
-    match fetch_remote_pricing() {
-        Ok(map) => {
-            if let Some(cache_path) = pricing_cache_path() {
-                let _ = save_pricing_cache(&cache_path, &map);
+}
+
+fn fetch_remote_pricing() -> Result<PricingSnapshot> {
+    let resp = ureq::get(LITELLM_PRICING_URL)
+        .timeout(std::time::Duration::from_secs(5))
+        .call()
+        .context("fetching remote pricing")?;
+    let json: serde_json::Value = resp.into_json().context("parsing pricing json")?;
+    let models = parse_litellm_pricing(&json);
+    if models.is_empty() {
+        anyhow::bail!("remote pricing dataset empty");
+    }
+    Ok(PricingSnapshot {
+        source: LITELLM_PRICING_URL.to_string(),
+        imported: false,
+        fetched_at: Some(Utc::now()),
+        models,
+    })
+}
+
+fn parse_litellm_pricing(json: &Value) -> HashMap<String, Pricing> {
+    let mut out = HashMap::new();
+    let Some(obj) = json.as_object() else {
+        return out;
+    };
+    for (key, val) in obj {
+        let per_million = |field: &str| {
+            val.get(field)
+                .and_then(|v| v.as_f64())
+                .map(|c| c * 1_000_000.0)
+        };
+        let (Some(input_per_million), Some(output_per_million)) = (
+            per_million("input_cost_per_token"),
+            per_million("output_cost_per_token"),
+        ) else {
+            continue;
+        };
+        out.insert(
+            key.clone(),
+            Pricing {
+                input_per_million,
+                output_per_million,
+                cache_create_per_million: per_million("cache_creation_input_token_cost")
+                    .unwrap_or(0.0),
+                cache_create_1h_per_million: per_million(
+                    "cache_creation_input_token_cost_above_1hr",
+                ),
+                cache_read_per_million: per_million("cache_read_input_token_cost").unwrap_or(0.0),
+                input_above_200k_per_million: per_million(
+                    "input_cost_per_token_above_200k_tokens",
+                ),
+                output_above_200k_per_million: per_million(
+                    "output_cost_per_token_above_200k_tokens",
+                ),
+                cache_create_above_200k_per_million: per_million(
+                    "cache_creation_input_token_cost_above_200k_tokens",
+                ),
+                cache_create_1h_above_200k_per_million: per_million(
+                    "cache_creation_input_token_cost_above_1hr_above_200k_tokens",
+                ),
+                cache_read_above_200k_per_million: per_million(
+                    "cache_read_input_token_cost_above_200k_tokens",
+                ),
+                ..Pricing::default()
+            },
+        );
+    }
+    out
+}
+
+fn load_bundled_pricing() -> Result<PricingSnapshot> {
+    let raw = include_str!("../data/pricing.json");
+    let models: HashMap<String, Pricing> =
+        serde_json::from_str(raw).context("parsing bundled pricing")?;
+    Ok(PricingSnapshot {
+        source: "bundled".to_string(),
+        imported: false,
+        fetched_at: None,
+        models,
+    })
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "ccusage", "ccusage-rs").map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+fn pricing_cache_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("pricing.json"))
+}
+
+fn load_pricing_file(path: &Path) -> Result<PricingSnapshot> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("reading pricing cache {}", path.display()))?;
+    let snapshot: PricingSnapshot = serde_json::from_str(&contents)
+        .with_context(|| format!("parsing pricing cache {}", path.display()))?;
+    Ok(snapshot)
+}
+
+fn save_pricing_cache(path: &Path, snapshot: &PricingSnapshot) -> Result<()> {
+    let contents = serde_json::to_vec_pretty(snapshot).context("serializing pricing cache")?;
+    write_cache_file(path, &contents)
+}
+
+fn pricing_history_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("pricing-history"))
+}
+
+/// Archived snapshots, oldest first. Unreadable files are skipped; archives
+/// written with the full table are cut down to Claude models on first read.
+fn load_pricing_history() -> Vec<PricingSnapshot> {
+    let Some(dir) = pricing_history_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<PricingSnapshot> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let mut snapshot = load_pricing_file(&path).ok()?;
+            let before = snapshot.models.len();
+            snapshot.models.retain(|key, _| is_claude_pricing_key(key));
+            if snapshot.models.len() != before
+                && let Ok(contents) = serde_json::to_vec(&snapshot)
+            {
+                let _ = write_cache_file(&path, &contents);
             }
-            Ok(map)
+            Some(snapshot)
+        })
+        .collect();
+    snapshots.sort_by_key(|snapshot| snapshot.fetched_at);
+    snapshots
+}
+
+/// Only Claude rates are archived; LiteLLM lists thousands of other models.
+fn is_claude_pricing_key(key: &str) -> bool {
+    normalize_model_for_pricing(key).starts_with("claude")
+}
+
+/// Keeps a dated copy of a fetched snapshot's Claude rates, unless they match
+/// the latest archived ones.
+fn archive_pricing_snapshot(snapshot: &PricingSnapshot) -> Result<()> {
+    let (Some(dir), Some(fetched_at)) = (pricing_history_dir(), snapshot.fetched_at) else {
+        return Ok(());
+    };
+    let models: HashMap<String, Pricing> = snapshot
+        .models
+        .iter()
+        .filter(|(key, _)| is_claude_pricing_key(key))
+        .map(|(key, pricing)| (key.clone(), pricing.clone()))
+        .collect();
+    if load_pricing_history()
+        .last()
+        .is_some_and(|latest| latest.models == models)
+    {
+        return Ok(());
+    }
+    let archived = PricingSnapshot {
+        source: snapshot.source.clone(),
+        imported: snapshot.imported,
+        fetched_at: snapshot.fetched_at,
+        models,
+    };
+    let path = dir.join(format!("{}.json", fetched_at.format("%Y-%m-%d")));
+    let contents = serde_json::to_vec(&archived).context("serializing pricing snapshot")?;
+    write_cache_file(&path, &contents)
+}
+
+/// Caches a freshly fetched snapshot and archives it for historical pricing.
+fn store_fetched_pricing(snapshot: &PricingSnapshot) -> Result<()> {
+    let path = pricing_cache_path().ok_or_else(|| anyhow!("no cache directory"))?;
+    save_pricing_cache(&path, snapshot)?;
+    archive_pricing_snapshot(snapshot)
+}
+
+/// Merges dated pricing tables, oldest first, into per-key rate histories.
+/// Each distinct rate set applies from the day it was first seen until the day
+/// before the next one; the oldest also covers earlier usage.
+fn merge_pricing_timeline(
+    timeline: impl Iterator<Item = (NaiveDate, HashMap<String, Pricing>)>,
+) -> HashMap<String, PricingHistory> {
+    let mut histories: HashMap<String, PricingHistory> = HashMap::new();
+    for (date, models) in timeline {
+        let mut combined: HashMap<String, Pricing> = HashMap::new();
+        for (key, pricing) in models {
+            combined
+                .entry(key.clone())
+                .or_insert_with(|| pricing.clone());
+            let normalized = normalize_model_for_pricing(&key);
+            combined.entry(normalized).or_insert(pricing);
         }
-        Err(err) => {
-            eprintln!("Failed to fetch pricing remotely ({err}); falling back to bundled pricing");
-            if let Some(cache_path) = pricing_cache_path() {
-                if let Ok(map) = load_pricing_file(&cache_path) {
-                    return Ok(map);
+        for (key, pricing) in combined {
+            let history = histories.entry(key).or_default();
+            match history.last_mut() {
+                Some(last) if last.same_rates(&pricing) => {}
+                Some(last) => {
+                    last.effective_until = date.pred_opt();
+                    history.push(Pricing {
+                        effective_from: Some(date),
+                        effective_until: None,
+                        ..pricing
+                    });
                 }
+                None => history.push(Pricing {
+                    effective_from: None,
+                    effective_until: None,
+                    ..pricing
+                }),
+            }
+        }
+    }
+    histories
+}
+
+/// Index over the current table. Archived snapshots are only read once a
+/// lookup needs rates from before the current table's date.
+fn build_pricing_index(snapshot: PricingSnapshot, config: Option<&PricingConfig>) -> PricingIndex {
+    let source = snapshot.describe();
+    // Bundled data has no fetch date and counts as today's rates
+    let current_date = snapshot
+        .fetched_at
+        .unwrap_or_else(Utc::now)
+        .date_naive();
+    let current = merge_pricing_timeline(std::iter::once((current_date, snapshot.models)));
+
+    let mut index = PricingIndex {
+        entries: sorted_by_prefix(current),
+        current_date,
+        dated_entries: OnceCell::new(),
+        overrides: Vec::new(),
+        aliases: HashMap::new(),
+        source,
+    };
+    if let Some(config) = config {
+        let overrides = config
+            .models
+            .iter()
+            .map(|(key, entry)| {
+                let mut history = match entry {
+                    ConfigPricing::Single(rates) => vec![rates.clone()],
+                    ConfigPricing::Dated(entries) => entries.clone(),
+                };
+                history.sort_by_key(|rates| rates.effective_from);
+                (normalize_model_for_pricing(key), history)
+            })
+            .collect();
+        index.overrides = sorted_by_prefix(overrides);
+        index.aliases = config
+            .aliases
+            .iter()
+            .map(|(from, to)| {
+                (
+                    normalize_model_for_pricing(from),
+                    normalize_model_for_pricing(to),
+                )
+            })
+            .collect();
+    }
+    index
+}
+
+fn watch_data_dirs(paths: &[PathBuf]) -> Result<(RecommendedWatcher, Receiver<()>)> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        if let Err(err) = res {
+            eprintln!("watch error: {err}");
+            return;
+        }
+        let _ = tx.send(());
+    })
+    .context("creating file watcher")?;
+
+    for dir in paths {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .with_context(|| format!("watching {}", dir.display()))?;
+    }
+
+    Ok((watcher, rx))
+}
+
+/// How a transcript is stored on disk; archived transcripts may be compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TranscriptEncoding {
+    Plain,
+    Gzip,
+    Zstd,
+}
+
+impl TranscriptEncoding {
+    const SUFFIXES: [(&'static str, TranscriptEncoding); 3] = [
+        (".jsonl", TranscriptEncoding::Plain),
+        (".jsonl.gz", TranscriptEncoding::Gzip),
+        (".jsonl.zst", TranscriptEncoding::Zstd),
+    ];
+
+    /// `None` for files that are not transcripts.
+    fn of(path: &Path) -> Option<Self> {
+        Self::split_name(path).map(|(_, encoding)| encoding)
+    }
+
+    /// File name without the `.jsonl[.gz|.zst]` suffix, and the encoding it names.
+    fn split_name(path: &Path) -> Option<(&str, Self)> {
+        let name = path.file_name()?.to_str()?;
+        Self::SUFFIXES.iter().find_map(|(suffix, encoding)| {
+            let stem = name.strip_suffix(suffix)?;
+            (!stem.is_empty()).then_some((stem, *encoding))
+        })
+    }
+
+    /// Reader over the decompressed lines, positioned `start` decompressed bytes in.
+    fn open(self, path: &Path, start: u64) -> Result<Box<dyn BufRead>> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut reader: Box<dyn BufRead> = match self {
+            TranscriptEncoding::Plain => {
+                let mut reader = BufReader::new(file);
+                reader.seek(SeekFrom::Start(start))?;
+                return Ok(Box::new(reader));
+            }
+            TranscriptEncoding::Gzip => {
+                Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+            }
+            TranscriptEncoding::Zstd => Box::new(BufReader::new(
+                zstd::Decoder::new(file)
+                    .with_context(|| format!("Failed to open {}", path.display()))?,
+            )),
+        };
+        // Compressed streams cannot seek, so skip the lines read before
+        std::io::copy(&mut (&mut reader).take(start), &mut std::io::sink())
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+        Ok(reader)
+    }
+}
+
+fn collect_jsonl_files(data_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in data_dirs {
+        let project_root = dir.join(PROJECTS_DIR);
+        if !project_root.exists() {
+            continue;
+        }
+        for entry in WalkDir::new(project_root)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let path = entry.into_path();
+            if TranscriptEncoding::of(&path).is_some() {
+                files.push(path);
             }
-            load_bundled_pricing()
         }
     }
+    files
+}
+
+fn load_events(
+    files: &[PathBuf],
+    index_path: Option<&Path>,
+    dedup: &mut Deduplicator,
+) -> Result<Vec<UsageEvent>> {
+    let mut index = index_path
+        .and_then(|path| load_event_index(path).ok())
+        .filter(|index| index.version == EVENT_INDEX_VERSION)
+        .unwrap_or_default();
+
+    let pending: Vec<(PathBuf, &PathBuf, Option<IndexedFile>)> = files
+        .iter()
+        .map(|file| {
+            let key = std::fs::canonicalize(file).unwrap_or_else(|_| file.clone());
+            let cached = index.files.remove(&key);
+            (key, file, cached)
+        })
+        .collect();
+    let scanned: Vec<(PathBuf, IndexedFile, Option<UsageEvent>, bool)> = pending
+        .into_par_iter()
+        .filter_map(|(key, file, cached)| {
+            let (entry, unterminated, changed) = index_file(file, cached)?;
+            Some((key, entry, unterminated, changed))
+        })
+        .collect();
+
+    let mut changed = scanned.iter().any(|(_, _, _, changed)| *changed);
+    // Keep entries for files outside this run's data dirs, as long as they still exist
+    let previous_len = index.files.len();
+    index.files.retain(|key, _| key.exists());
+    changed |= index.files.len() != previous_len;
+
+    let mut keys = Vec::with_capacity(scanned.len());
+    for (key, entry, unterminated, _) in scanned {
+        keys.push((key.clone(), unterminated));
+        index.files.insert(key, entry);
+    }
+    if let Some(path) = index_path
+        && changed
+    {
+        index.version = EVENT_INDEX_VERSION;
+        if let Err(err) = save_event_index(path, &index) {
+            eprintln!("Failed to write event index ({err:#})");
+        }
+    }
+
+    let mut per_file: Vec<(PathBuf, Vec<UsageEvent>)> = keys
+        .into_iter()
+        .filter_map(|(key, unterminated)| {
+            let mut file_events = index.files.remove(&key)?.events;
+            file_events.extend(unterminated);
+            Some((key, file_events))
+        })
+        .collect();
+
+    // Visit files oldest-first so the original transcript keeps an entry and
+    // resumed/forked copies are the ones dropped.
+    per_file.sort_by_cached_key(|(file, file_events)| {
+        (file_events.iter().map(|e| e.timestamp).min(), file.clone())
+    });
+    let events = per_file
+        .into_iter()
+        .flat_map(|(_, file_events)| dedup.retain_new(file_events))
+        .collect();
+    Ok(events)
 }
 
-fn fetch_remote_pricing() -> Result<HashMap<String, Pricing>> {
-    // LiteLLM pricing dataset
-    const URL: &str = "https://raw.githubusercontent.com/BerriAI/litellm/main/model_prices_and_context_window.json";
-    let resp = ureq::get(URL)
-        .timeout(std::time::Duration::from_secs(5))
-        .call()
-        .context("fetching remote pricing")?;
-    let json: serde_json::Value = resp.into_json().context("parsing pricing json")?;
-    let mut out = HashMap::new();
-    if let Some(obj) = json.as_object() {
-        for (key, val) in obj {
-            let Some(input_per_token) = val.get("input_cost_per_token").and_then(|v| v.as_f64())
-            else {
-                continue;
-            };
-            let Some(output_per_token) = val.get("output_cost_per_token").and_then(|v| v.as_f64())
-            else {
-                continue;
-            };
-            let cache_create = val
-                .get("cache_creation_input_token_cost")
-                .and_then(|v| v.as_f64())
-                .unwrap_or(0.0);
-            let cache_read = val
-                .get("cache_read_input_token_cost")
-                .and_then(|v| v.as_f64())
-                .unwrap_or(0.0);
-            out.insert(
-                key.clone(),
-                Pricing {
-                    input_per_million: input_per_token * 1_000_000.0,
-                    output_per_million: output_per_token * 1_000_000.0,
-                    cache_create_per_million: cache_create * 1_000_000.0,
-                    cache_read_per_million: cache_read * 1_000_000.0,
-                },
-            );
+/// Parsed events for one transcript, stored in the on-disk event index.
+#[derive(Serialize, Deserialize)]
+struct IndexedFile {
+    size: u64,
+    mtime_ns: u64,
+    /// Bytes parsed so far; always ends on a line boundary
+    offset: u64,
+    /// `prefix_digest` of the first `offset` bytes, to tell an append from a rewrite
+    #[serde(default)]
+    prefix_digest: u64,
+    events: Vec<UsageEvent>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct EventIndex {
+    version: u32,
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+/// Returns the up-to-date index entry for `path`, the event on an unterminated last line
+/// (kept out of the index until its newline is written), and whether anything had to be
+/// parsed. Unchanged files come straight from `cached`; appended files only parse their
+/// new tail, and files rewritten in place are read again from the start.
+fn index_file(
+    path: &Path,
+    cached: Option<IndexedFile>,
+) -> Option<(IndexedFile, Option<UsageEvent>, bool)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let size = meta.len();
+    let mtime_ns = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+
+    let (mut entry, changed) = match cached {
+        Some(entry) if entry.size == size && entry.mtime_ns == mtime_ns => (entry, false),
+        // Plain transcripts are appended to; a changed archive is read again from the start
+        Some(entry)
+            if size >= entry.offset
+                && TranscriptEncoding::of(path) == Some(TranscriptEncoding::Plain)
+                && prefix_digest(path, entry.offset).ok() == Some(entry.prefix_digest) =>
+        {
+            (entry, true)
         }
+        _ => (
+            IndexedFile {
+                size: 0,
+                mtime_ns: 0,
+                offset: 0,
+                prefix_digest: 0,
+                events: Vec::new(),
+            },
+            true,
+        ),
+    };
+    // Costs depend on the current pricing and cost mode, so they are never trusted from disk
+    for ev in &mut entry.events {
+        label_project(ev);
+        price_event(ev);
     }
-    if out.is_empty() {
-        anyhow::bail!("remote pricing dataset empty");
+    let mut unterminated = None;
+    if changed {
+        let (offset, mut new_events, last) = read_new_events(path, entry.offset).ok()?;
+        entry.events.append(&mut new_events);
+        entry.offset = offset;
+        entry.size = size;
+        entry.mtime_ns = mtime_ns;
+        if TranscriptEncoding::of(path) == Some(TranscriptEncoding::Plain) {
+            entry.prefix_digest = prefix_digest(path, offset).ok()?;
+        }
+        unterminated = last;
+    } else if entry.offset < size
+        && TranscriptEncoding::of(path) == Some(TranscriptEncoding::Plain)
+    {
+        unterminated = read_new_events(path, entry.offset).ok()?.2;
     }
-    Ok(out)
+    Some((entry, unterminated, changed))
 }
 
-fn load_bundled_pricing() -> Result<HashMap<String, Pricing>> {
-    let raw = include_str!("../data/pricing.json");
-    let map: HashMap<String, Pricing> =
-        serde_json::from_str(raw).context("parsing bundled pricing")?;
-    Ok(map)
+fn event_index_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("events.json"))
 }
 
-fn pricing_cache_path() -> Option<PathBuf> {
-    ProjectDirs::from("com", "ccusage", "ccusage-rs")
-        .map(|dirs| dirs.cache_dir().join("pricing.json"))
+fn load_event_index(path: &Path) -> Result<EventIndex> {
+    let contents = std::fs::read(path)
+        .with_context(|| format!("reading event index {}", path.display()))?;
+    let index: EventIndex = serde_json::from_slice(&contents)
+        .with_context(|| format!("parsing event index {}", path.display()))?;
+    Ok(index)
 }
 
-fn load_pricing_file(path: &Path) -> Result<HashMap<String, Pricing>> {
-    let contents = std::fs::read_to_string(path)
-        .with_context(|| format!("reading pricing cache {}", path.display()))?;
-    let map: HashMap<String, Pricing> = serde_json::from_str(&contents)
-        .with_context(|| format!("parsing pricing cache {}", path.display()))?;
-    Ok(map)
+fn save_event_index(path: &Path, index: &EventIndex) -> Result<()> {
+    let contents = serde_json::to_vec(index).context("serializing event index")?;
+    write_cache_file(path, &contents)
 }
 
-fn save_pricing_cache(path: &Path, map: &HashMap<String, Pricing>) -> Result<()> {
+/// Write then rename so a concurrent run never sees a half-written cache file.
+fn write_cache_file(path: &Path, contents: &[u8]) -> Result<()> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("creating cache dir {}", parent.display()))?;
     }
-    let contents = serde_json::to_string_pretty(map).context("serializing pricing cache")?;
-    std::fs::write(path, contents)
-        .with_context(|| format!("writing pricing cache {}", path.display()))?;
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, contents)
+        .with_context(|| format!("writing cache file {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("replacing cache file {}", path.display()))?;
     Ok(())
 }
 
-fn build_pricing_index(map: HashMap<String, Pricing>) -> PricingIndex {
-    let mut combined: HashMap<String, Pricing> = HashMap::new();
-    for (key, pricing) in map {
-        combined
-            .entry(key.clone())
-            .or_insert_with(|| pricing.clone());
-        let normalized = normalize_model_for_pricing(&key);
-        combined.entry(normalized).or_insert(pricing);
-    }
-    PricingIndex::from_map(combined)
+fn statusline_cache_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("statusline.json"))
 }
 
-fn watch_data_dirs(paths: &[PathBuf]) -> Result<(RecommendedWatcher, Receiver<()>)> {
-    let (tx, rx) = channel();
-    let mut watcher = notify::recommended_watcher(move |res| {
-        if let Err(err) = res {
-            eprintln!("watch error: {err}");
-            return;
+fn run_cache_command(args: &CacheArgs) -> Result<()> {
+    match args.action {
+        CacheAction::Clear { pricing } => {
+            let mut removed = 0;
+            let mut paths = vec![event_index_path(), statusline_cache_path()];
+            if pricing {
+                paths.push(pricing_cache_path());
+            }
+            for path in paths.into_iter().flatten() {
+                if path.exists() {
+                    std::fs::remove_file(&path)
+                        .with_context(|| format!("removing {}", path.display()))?;
+                    println!("Removed {}", path.display());
+                    removed += 1;
+                }
+            }
+            if removed == 0 {
+                println!("Cache is already empty");
+            }
         }
-        let _ = tx.send(());
-    })
-    .context("creating file watcher")?;
-
-    for dir in paths {
-        watcher
-            .watch(dir, RecursiveMode::Recursive)
-            .with_context(|| format!("watching {}", dir.display()))?;
     }
-
-    Ok((watcher, rx))
+    Ok(())
 }
 
-fn collect_jsonl_files(data_dirs: &[PathBuf]) -> Vec<PathBuf> {
-    let mut files = Vec::new();
-    for dir in data_dirs {
-        let project_root = dir.join(PROJECTS_DIR);
-        if !project_root.exists() {
-            continue;
+fn run_pricing_command(
+    args: &PricingArgs,
+    offline: bool,
+    config: Option<&PricingConfig>,
+    json: bool,
+) -> Result<()> {
+    match &args.action {
+        PricingAction::Update => {
+            let snapshot = fetch_remote_pricing()?;
+            store_fetched_pricing(&snapshot)?;
+            println!(
+                "Saved {} models to {}",
+                snapshot.models.len(),
+                pricing_cache_path().unwrap_or_default().display()
+            );
         }
-        for entry in WalkDir::new(project_root)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let path = entry.into_path();
-            if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
-                files.push(path);
+        PricingAction::Import { path } => {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            let json: Value = serde_json::from_str(&contents)
+                .with_context(|| format!("parsing {}", path.display()))?;
+            let models = parse_litellm_pricing(&json);
+            if models.is_empty() {
+                anyhow::bail!("{} has no LiteLLM pricing entries", path.display());
+            }
+            let snapshot = PricingSnapshot {
+                source: path.canonicalize().unwrap_or_else(|_| path.clone()).display().to_string(),
+                imported: true,
+                fetched_at: Some(Utc::now()),
+                models,
+            };
+            let cache_path = pricing_cache_path().ok_or_else(|| anyhow!("no cache directory"))?;
+            save_pricing_cache(&cache_path, &snapshot)?;
+            println!("Imported {} models into {}", snapshot.models.len(), cache_path.display());
+        }
+        PricingAction::Show { model, date } => {
+            let index = build_pricing_index(load_pricing(offline)?, config);
+            let date = parse_date_opt(date.as_deref())?.unwrap_or_else(|| Utc::now().date_naive());
+            let normalized = normalize_model_for_pricing(model);
+            let found = index
+                .lookup(&normalized, date)
+                .ok_or_else(|| anyhow!("no pricing for model {model} on {date}"))?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&found)?);
+            } else {
+                print_pricing_match(&found);
+            }
+        }
+        PricingAction::List => {
+            let index = build_pricing_index(load_pricing(offline)?, config);
+            let models = index.claude_models();
+            if json {
+                let entries: BTreeMap<_, _> = models.iter().map(|(key, (pricing, _))| (*key, pricing)).collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+                return Ok(());
             }
+            let mut table = SimpleTable::new(vec![
+                "Model", "Input", "Output", "C/W 5m", "C/W 1h", "C/R", ">200k In", ">200k Out", "Source",
+            ])
+            .header_style(|s| s.cyan().bold());
+            for (key, (pricing, origin)) in models {
+                let tier = pricing.long_context_tier();
+                table.add_row(vec![
+                    key.to_string(),
+                    format_rate(pricing.input_per_million),
+                    format_rate(pricing.output_per_million),
+                    format_rate(pricing.cache_create_per_million),
+                    format_rate(pricing.cache_create_1h_rate()),
+                    format_rate(pricing.cache_read_per_million),
+                    tier.as_ref().map_or_else(|| "-".into(), |t| format_rate(t.input_per_million)),
+                    tier.as_ref().map_or_else(|| "-".into(), |t| format_rate(t.output_per_million)),
+                    if origin == "config" { "config".into() } else { "data".into() },
+                ]);
+            }
+            println!("{} {}", "Pricing per million tokens:".bold(), index.source.dimmed());
+            table.print();
         }
     }
-    files
+    Ok(())
 }
 
-fn load_events(files: &[PathBuf]) -> Result<Vec<UsageEvent>> {
-    let events: Vec<UsageEvent> = files
-        .par_iter()
-        .flat_map(|file| {
-            let project = extract_project_name(file);
-            let session_id = file
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown")
-                .to_string();
-
-            let fh = match File::open(file) {
-                Ok(f) => f,
-                Err(_) => return Vec::new(),
-            };
-            let mut file_events = Vec::new();
-            for line in BufReader::new(fh).lines() {
-                let line = match line {
-                    Ok(l) => l,
-                    Err(_) => continue,
-                };
-                // Fast pre-filter: skip lines without usage data
-                if !line.contains("input_tokens") {
-                    continue;
-                }
-                let parsed: RawRecord = match serde_json::from_str(&line) {
-                    Ok(v) => v,
-                    Err(_) => continue,
-                };
-                if let Some(event) = to_usage_event(parsed, project.clone(), session_id.clone()) {
-                    file_events.push(event);
-                }
-            }
-            file_events
-        })
-        .collect();
-    Ok(events)
+fn print_pricing_match(found: &PricingMatch) {
+    let pricing = &found.pricing;
+    println!("{:<16}{}", "Model", found.model);
+    if let Some(target) = found.alias_of {
+        println!("{:<16}{}", "Alias of", target);
+    }
+    println!("{:<16}{}", "Matched key", found.matched_key);
+    println!("{:<16}{}", "Source", found.origin);
+    if pricing.effective_from.is_some() || pricing.effective_until.is_some() {
+        let day = |date: Option<NaiveDate>| date.map_or_else(|| "(open)".to_string(), |d| d.to_string());
+        println!(
+            "{:<16}{} to {}",
+            "Effective",
+            day(pricing.effective_from),
+            day(pricing.effective_until)
+        );
+    }
+    let rows = [
+        ("Input", pricing.input_per_million),
+        ("Output", pricing.output_per_million),
+        ("Cache write 5m", pricing.cache_create_per_million),
+        ("Cache write 1h", pricing.cache_create_1h_rate()),
+        ("Cache read", pricing.cache_read_per_million),
+    ];
+    for (label, rate) in rows {
+        println!("{:<16}{} / 1M tokens", label, format_rate(rate));
+    }
+    if let Some(tier) = pricing.long_context_tier() {
+        println!(
+            "{:<16}input {}, output {}, cache write {}, cache read {}",
+            "Above 200k",
+            format_rate(tier.input_per_million),
+            format_rate(tier.output_per_million),
+            format_rate(tier.cache_create_per_million),
+            format_rate(tier.cache_read_per_million)
+        );
+    }
 }
 
 struct LiveEventSource {
     data_dirs: Vec<PathBuf>,
     offsets: HashMap<PathBuf, u64>,
     events: Vec<UsageEvent>,
+    dedup: Deduplicator,
 }
 
 impl LiveEventSource {
@@ -1164,6 +3411,7 @@ impl LiveEventSource {
         data_dirs: Vec<PathBuf>,
         files: &[PathBuf],
         events: Vec<UsageEvent>,
+        dedup: Deduplicator,
     ) -> Result<Self> {
         let mut offsets = HashMap::new();
         for file in files {
@@ -1176,6 +3424,7 @@ impl LiveEventSource {
             data_dirs,
             offsets,
             events,
+            dedup,
         })
     }
 
@@ -1183,15 +3432,26 @@ impl LiveEventSource {
         &self.events
     }
 
+    fn duplicates_removed(&self) -> usize {
+        self.dedup.removed()
+    }
+
     fn refresh(&mut self) -> Result<()> {
         let files = collect_jsonl_files(&self.data_dirs);
         for file in files {
+            // Archives are written once; only plain transcripts are appended to
+            if TranscriptEncoding::of(&file) != Some(TranscriptEncoding::Plain)
+                && self.offsets.contains_key(&file)
+            {
+                continue;
+            }
             let current_len = std::fs::metadata(&file)
                 .with_context(|| format!("stat {}", file.display()))?
                 .len();
             let offset = self.offsets.get(&file).copied().unwrap_or(0);
             let start = if current_len < offset { 0 } else { offset };
-            let (new_offset, mut new_events) = read_new_events(&file, start)?;
+            let (new_offset, new_events, _) = read_new_events(&file, start)?;
+            let mut new_events = self.dedup.retain_new(new_events);
             if !new_events.is_empty() {
                 self.events.append(&mut new_events);
             }
@@ -1201,19 +3461,43 @@ impl LiveEventSource {
     }
 }
 
-fn read_new_events(path: &Path, start: u64) -> Result<(u64, Vec<UsageEvent>)> {
-    let project = extract_project_name(path);
-    let session_id = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown")
-        .to_string();
-
-    let mut events = Vec::new();
+/// Parses the transcript from byte `start`. Returns the offset after the last complete line,
+/// the events on complete lines, and the event on a final line with no newline yet, which a
+/// one-shot run counts but a live reader leaves until the writer finishes it.
+/// FNV-1a over the first and last `PREFIX_DIGEST_BYTES` of the file's first `len` bytes.
+fn prefix_digest(path: &Path, len: u64) -> Result<u64> {
     let mut file =
         File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
-    let mut reader = BufReader::new(&mut file);
-    reader.seek(SeekFrom::Start(start))?;
+    let head = len.min(PREFIX_DIGEST_BYTES);
+    let tail_start = len.saturating_sub(PREFIX_DIGEST_BYTES).max(head);
+    let mut bytes = Vec::with_capacity((head + len - tail_start) as usize);
+    (&mut file).take(head).read_to_end(&mut bytes)?;
+    file.seek(SeekFrom::Start(tail_start))?;
+    file.take(len - tail_start).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != head + len - tail_start {
+        anyhow::bail!("{} is shorter than its indexed prefix", path.display());
+    }
+    Ok(bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    }))
+}
+
+fn read_new_events(
+    path: &Path,
+    start: u64,
+) -> Result<(u64, Vec<UsageEvent>, Option<UsageEvent>)> {
+    let project_dir = extract_project_name(path);
+    // Hook payloads may point at a transcript with any name; read it as plain text
+    let (session_id, encoding) = TranscriptEncoding::split_name(path)
+        .unwrap_or_else(|| {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("unknown");
+            (stem, TranscriptEncoding::Plain)
+        });
+    let session_id = session_id.to_string();
+
+    let mut events = Vec::new();
+    let mut unterminated = None;
+    let mut reader = encoding.open(path, start)?;
     let mut position = start;
 
     loop {
@@ -1222,66 +3506,288 @@ fn read_new_events(path: &Path, start: u64) -> Result<(u64, Vec<UsageEvent>)> {
         if bytes == 0 {
             break;
         }
-        position += bytes as u64;
-        if buf.trim().is_empty() {
+        // A plain transcript may still be appending its last line; archives are complete
+        let complete = buf.ends_with('\n') || encoding != TranscriptEncoding::Plain;
+        if complete {
+            position += bytes as u64;
+        }
+        // Fast pre-filter: skip lines without usage data or an API error flag
+        if !buf.contains("input_tokens") && !buf.contains("isApiErrorMessage") {
             continue;
         }
         let parsed: RawRecord = match serde_json::from_str(&buf) {
             Ok(v) => v,
             Err(_) => continue,
         };
-        if let Some(event) = to_usage_event(parsed, project.clone(), session_id.clone()) {
-            events.push(event);
+        let event = to_usage_event(parsed, project_dir.clone(), session_id.clone());
+        if complete {
+            events.extend(event);
+        } else {
+            unterminated = event;
         }
     }
 
-    Ok((position, events))
+    Ok((position, events, unterminated))
 }
 
-fn to_usage_event(raw: RawRecord, project: String, session_id: String) -> Option<UsageEvent> {
+fn to_usage_event(raw: RawRecord, project_dir: String, session_id: String) -> Option<UsageEvent> {
     let ts = parse_timestamp(&raw.timestamp)?;
     let message = raw.message;
-    let usage = message
-        .as_ref()
-        .and_then(|m| m.usage.as_ref())
-        .or(raw.usage.as_ref())?;
+    let dedup_key = match (message.as_ref().and_then(|m| m.id.as_ref()), &raw.request_id) {
+        (Some(message_id), Some(request_id)) => Some(format!("{message_id}:{request_id}")),
+        _ => None,
+    };
+    let flagged = raw.is_api_error_message == Some(true);
+    let api_error = detect_api_error(flagged, message.as_ref());
+    let no_usage = RawUsage::default();
+    let usage = match message.as_ref().and_then(|m| m.usage.as_ref()).or(raw.usage.as_ref()) {
+        Some(usage) => usage,
+        // Error lines are kept even when Claude Code logged no usage for them
+        None if api_error.is_some() => &no_usage,
+        None => return None,
+    };
 
     let input_tokens = usage.input_tokens.unwrap_or(0);
     let output_tokens = usage.output_tokens.unwrap_or(0);
-    let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0);
+    // Older transcripts only have the flat count, which was always a 5-minute write;
+    // a flat count above the two tiers bills the difference as 5-minute writes too
+    let flat = usage.cache_creation_input_tokens.unwrap_or(0);
+    let (cache_creation_5m_tokens, cache_creation_1h_tokens) = match &usage.cache_creation {
+        Some(tiers) => {
+            let five_minute = tiers.ephemeral_5m_input_tokens.unwrap_or(0);
+            let one_hour = tiers.ephemeral_1h_input_tokens.unwrap_or(0);
+            let untiered = flat.saturating_sub(five_minute + one_hour);
+            (five_minute + untiered, one_hour)
+        }
+        None => (flat, 0),
+    };
+    let cache_creation_tokens = cache_creation_5m_tokens + cache_creation_1h_tokens;
     let cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0);
-    let cost = match COST_MODE.get().copied().unwrap_or(CostMode::Auto) {
-        CostMode::Calculate => calculate_cost(
-            message.as_ref().and_then(|m| m.model.as_deref()),
-            input_tokens,
-            output_tokens,
-            cache_creation_tokens,
-            cache_read_tokens,
-        ),
-        CostMode::PreferField | CostMode::Auto => raw.cost_usd.unwrap_or_else(|| {
-            calculate_cost(
-                message.as_ref().and_then(|m| m.model.as_deref()),
-                input_tokens,
-                output_tokens,
-                cache_creation_tokens,
-                cache_read_tokens,
-            )
-        }),
+    let tool_uses = match message.as_ref().and_then(|m| m.content.as_ref()) {
+        Some(RawContent::Blocks(blocks)) => blocks
+            .iter()
+            .filter(|b| b.kind == "tool_use")
+            .filter_map(|b| {
+                Some(ToolUse {
+                    id: b.id.clone(),
+                    name: b.name.clone()?,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
     };
 
-    Some(UsageEvent {
+    let mut event = UsageEvent {
         timestamp: ts,
-        project,
+        project: String::new(),
+        project_dir,
+        cwd: raw.cwd,
+        git_branch: raw.git_branch.filter(|b| !b.is_empty()),
+        is_sidechain: raw.is_sidechain.unwrap_or(false),
+        version: raw.version.filter(|v| !v.is_empty()),
         session_id: raw.session_id.unwrap_or(session_id),
         model: message.and_then(|m| m.model),
         input_tokens,
         output_tokens,
         cache_creation_tokens,
+        cache_creation_5m_tokens,
+        cache_creation_1h_tokens,
         cache_read_tokens,
-        cost_usd: cost,
+        cost_usd: Money::ZERO,
+        costs: CostBreakdown::default(),
+        converted_cost: None,
+        long_context: false,
+        unpriced: false,
+        logged_cost_usd: raw.cost_usd,
+        dedup_key,
+        tool_uses,
+        api_error,
+    };
+    label_project(&mut event);
+    price_event(&mut event);
+    Some(event)
+}
+
+/// Flagged API errors, plus `<synthetic>` messages announcing an API error or usage limit.
+fn detect_api_error(flagged: bool, message: Option<&RawMessage>) -> Option<ApiError> {
+    let text = match message.and_then(|m| m.content.as_ref()) {
+        Some(RawContent::Blocks(blocks)) => blocks.iter().find_map(|b| b.text.as_deref()),
+        _ => None,
+    }
+    .unwrap_or_default()
+    .trim();
+    let lower = text.to_lowercase();
+    let synthetic = message.and_then(|m| m.model.as_deref()) == Some("<synthetic>");
+    let limit = lower.contains("limit reached");
+    if !flagged && !(synthetic && (limit || lower.starts_with("api error"))) {
+        return None;
+    }
+    let kind = if limit {
+        ApiErrorKind::UsageLimit
+    } else if lower.contains("overloaded") || lower.contains(" 529") {
+        ApiErrorKind::Overloaded
+    } else {
+        ApiErrorKind::ApiError
+    };
+    Some(ApiError {
+        kind,
+        message: text.chars().take(200).collect(),
     })
 }
 
+/// Reset time of a usage-limit message: `...limit reached|<unix seconds>` in older
+/// transcripts, else "resets 3pm" / "reset at 3:30pm (Europe/Paris)" taken as the
+/// next such time after the message, in the report's timezone when no zone is named.
+fn parse_reset_time(text: &str, at: DateTime<Utc>, tz: &Tz) -> Option<DateTime<Utc>> {
+    if let Some((_, epoch)) = text.rsplit_once('|')
+        && let Ok(secs) = epoch.trim().parse::<i64>()
+    {
+        return DateTime::from_timestamp(secs, 0);
+    }
+    let lower = text.to_lowercase();
+    let start = ["reset at ", "resets at ", "resets "]
+        .iter()
+        .find_map(|marker| lower.find(marker).map(|i| i + marker.len()))?;
+    let rest = &lower[start..];
+    let word = rest
+        .split_whitespace()
+        .next()?
+        .trim_end_matches(['.', ',', ')']);
+    let (clock, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(false)),
+        (_, Some(clock)) => (clock, Some(true)),
+        _ => (word, None),
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    let hour = match pm {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+    let zone: Tz = text
+        .split_once('(')
+        .and_then(|(_, zone)| zone.split_once(')'))
+        .and_then(|(zone, _)| zone.trim().parse().ok())
+        .unwrap_or(*tz);
+    let local = at.with_timezone(&zone);
+    let time = chrono::NaiveTime::from_hms_opt(hour, minute, 0)?;
+    let mut reset = local.date_naive().and_time(time).and_local_timezone(zone).earliest()?;
+    if reset <= local {
+        reset += ChronoDuration::days(1);
+    }
+    Some(reset.with_timezone(&Utc))
+}
+
+/// Labels the event with its recorded `cwd`, or with the decoded project
+/// directory name for lines that lack one.
+fn label_project(ev: &mut UsageEvent) {
+    let path = ev
+        .cwd
+        .as_deref()
+        .map(PathBuf::from)
+        .or_else(|| decode_project_dir(&ev.project_dir));
+    ev.project = match path {
+        Some(path) => display_project_path(&path),
+        None => ev.project_dir.clone(),
+    };
+}
+
+/// Path relative to the configured project root, else `~/`-relative under the
+/// home directory, else as is.
+fn display_project_path(path: &Path) -> String {
+    if let Some(root) = PROJECT_ROOT.get()
+        && let Ok(rest) = path.strip_prefix(root)
+        && !rest.as_os_str().is_empty()
+    {
+        return rest.display().to_string();
+    }
+    if let Some(home) = home_dir()
+        && let Ok(rest) = path.strip_prefix(&home)
+    {
+        if rest.as_os_str().is_empty() {
+            return "~".into();
+        }
+        return format!("~/{}", rest.display());
+    }
+    path.display().to_string()
+}
+
+/// Best-effort inverse of Claude's project directory naming, which turns path
+/// separators into dashes. Dashes are kept where they join the longest name that
+/// exists on disk; the rest count as separators. Each name is probed once per run.
+fn decode_project_dir(name: &str) -> Option<PathBuf> {
+    let decoded = DECODED_PROJECT_DIRS.get_or_init(Default::default);
+    if let Some(path) = decoded.lock().expect("project dir cache").get(name) {
+        return path.clone();
+    }
+    let path = probe_project_dir(name);
+    decoded
+        .lock()
+        .expect("project dir cache")
+        .insert(name.to_string(), path.clone());
+    path
+}
+
+fn probe_project_dir(name: &str) -> Option<PathBuf> {
+    let parts: Vec<&str> = name.strip_prefix('-')?.split('-').collect();
+    let mut path = PathBuf::from("/");
+    let mut start = 0;
+    while start < parts.len() {
+        let end = (start + 2..=parts.len())
+            .rev()
+            .find(|&end| path.join(parts[start..end].join("-")).exists())
+            .unwrap_or(start + 1);
+        path.push(parts[start..end].join("-"));
+        start = end;
+    }
+    Some(path)
+}
+
+/// `--project` accepts the report label, the recorded `cwd` or the encoded directory name.
+fn project_matches(ev: &UsageEvent, project: &str) -> bool {
+    ev.project == project
+        || ev.project_dir == project
+        || ev
+            .cwd
+            .as_deref()
+            .is_some_and(|cwd| Path::new(cwd) == expand_tilde(Path::new(project)))
+}
+
+/// Resolves `cost_usd` and its per-category split according to the cost mode.
+/// A logged `costUSD` is split in proportion to the calculated category costs.
+fn price_event(ev: &mut UsageEvent) {
+    let priced = calculate_cost(
+        ev.model.as_deref(),
+        ev.timestamp,
+        ev.input_tokens,
+        ev.output_tokens,
+        ev.cache_creation_5m_tokens,
+        ev.cache_creation_1h_tokens,
+        ev.cache_read_tokens,
+    );
+    let (calculated, long_context) = priced.unwrap_or_default();
+    let logged = match COST_MODE.get().copied().unwrap_or(CostMode::Auto) {
+        CostMode::Calculate => None,
+        CostMode::PreferField => ev.logged_cost_usd,
+        CostMode::Auto => ev.logged_cost_usd.filter(|cost| *cost > 0.0),
+    };
+    let (cost, costs) = match logged.map(Money::from_usd) {
+        Some(logged) => (logged, calculated.apportion(logged)),
+        None => (calculated.total(), calculated),
+    };
+    ev.cost_usd = cost;
+    ev.costs = costs;
+    ev.converted_cost = CURRENCY
+        .get()
+        .map(|currency| currency.convert(cost, ev.timestamp));
+    ev.long_context = long_context;
+    ev.unpriced =
+        priced.is_none() && logged.is_none() && ev.model.is_some() && event_total_tokens(ev) > 0;
+}
+
 fn parse_timestamp(ts: &str) -> Option<DateTime<Utc>> {
     chrono::DateTime::parse_from_rfc3339(ts)
         .ok()
@@ -1302,56 +3808,202 @@ fn normalize_model_for_pricing(model: &str) -> String {
     m
 }
 
+/// Bills each token category once at the rate in effect on the event's day.
+/// `None` when the model has no pricing.
+/// A prompt above the long-context threshold bills the whole request at the
+/// model's long-context tier, if it has one; the flag reports whether it did.
 fn calculate_cost(
     model: Option<&str>,
+    at: DateTime<Utc>,
     input: u64,
     output: u64,
-    cache_creation: u64,
+    cache_creation_5m: u64,
+    cache_creation_1h: u64,
     cache_read: u64,
-) -> f64 {
-    let Some(model_name) = model else { return 0.0 };
+) -> Option<(CostBreakdown, bool)> {
+    let model_name = model?;
     let normalized = normalize_model_for_pricing(model_name);
-    let pricing = PRICING_INDEX.get().and_then(|idx| idx.find(&normalized));
+    let pricing = PRICING_INDEX
+        .get()
+        .and_then(|idx| idx.find(&normalized, at.date_naive()));
+
+    let pricing = pricing?;
+    let prompt_tokens = input + cache_creation_5m + cache_creation_1h + cache_read;
+    let long_context_tier = (prompt_tokens > LONG_CONTEXT_THRESHOLD_TOKENS)
+        .then(|| pricing.long_context_tier())
+        .flatten();
+    let rates = long_context_tier.as_ref().unwrap_or(&pricing);
+    // Tokens times a per-million rate is an amount in micro-dollars
+    let per_token = |tokens: u64, per_million: f64| Money::from_micros(tokens as f64 * per_million);
+    let costs = CostBreakdown {
+        input_cost: per_token(input, rates.input_per_million),
+        output_cost: per_token(output, rates.output_per_million),
+        cache_write_cost: per_token(cache_creation_5m, rates.cache_create_per_million)
+            + per_token(cache_creation_1h, rates.cache_create_1h_rate()),
+        cache_read_cost: per_token(cache_read, rates.cache_read_per_million),
+    };
+    Some((costs, long_context_tier.is_some()))
+}
+
+fn build_daily_report(
+    events: &[UsageEvent],
+    tz: &Tz,
+    locale: &Locale,
+    range: &RangeFilter,
+    order: Order,
+    instances: bool,
+    breakdown: bool,
+) -> Result<JsonReport> {
+    let mut map: BTreeMap<(NaiveDate, Option<String>), RowAccumulator> = BTreeMap::new();
+
+    for ev in events {
+        if !range.includes(ev) {
+            continue;
+        }
+        let date = ev.timestamp.with_timezone(tz).date_naive();
+        if !in_range(date, range.since, range.until) {
+            continue;
+        }
+        let key = if instances {
+            (date, Some(ev.project.clone()))
+        } else {
+            (date, None)
+        };
+        let display_key = if let Some(project) = key.1.as_ref() {
+            format!("{} ({})", date.format("%Y-%m-%d"), project)
+        } else {
+            date.format("%Y-%m-%d").to_string()
+        };
+        let entry = map
+            .entry(key)
+            .or_insert_with(|| RowAccumulator::new(display_key));
+        entry.add_event(ev);
+    }
+
+    let mut rows: Vec<Row> = map.into_values().map(|acc| acc.finish()).collect();
+    sort_rows(&mut rows, order);
+    let totals = calculate_totals(&rows);
+    let model_breakdowns = if breakdown {
+        aggregate_models_from_rows(&rows)
+    } else {
+        Vec::new()
+    };
+    let unpriced_models = collect_unpriced_models(rows.iter().flat_map(|row| &row.model_breakdowns));
+    Ok(JsonReport {
+        kind: "daily",
+        timezone: tz.name().to_string(),
+        locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
+        since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
+        until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
+        rows,
+        totals,
+        model_breakdowns,
+        unpriced_models,
+        duplicates_removed: 0,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_weekly_report(
+    events: &[UsageEvent],
+    tz: &Tz,
+    locale: &Locale,
+    range: &RangeFilter,
+    order: Order,
+    instances: bool,
+    start_of_week: WeekStart,
+    breakdown: bool,
+) -> Result<JsonReport> {
+    let mut map: BTreeMap<(NaiveDate, Option<String>), RowAccumulator> = BTreeMap::new();
+
+    for ev in events {
+        if !range.includes(ev) {
+            continue;
+        }
+        let date = ev.timestamp.with_timezone(tz).date_naive();
+        if !in_range(date, range.since, range.until) {
+            continue;
+        }
+        let week_start = week_start_for_date(date, start_of_week);
+        let key = if instances {
+            (week_start, Some(ev.project.clone()))
+        } else {
+            (week_start, None)
+        };
+        let display_key = if let Some(project) = key.1.as_ref() {
+            format!("{} ({})", week_start.format("%Y-%m-%d"), project)
+        } else {
+            week_start.format("%Y-%m-%d").to_string()
+        };
+        let entry = map
+            .entry(key)
+            .or_insert_with(|| RowAccumulator::new(display_key));
+        entry.add_event(ev);
+    }
 
-    let Some(pricing) = pricing else { return 0.0 };
-    let input_tokens = input + cache_creation + cache_read;
-    let input_cost = (input_tokens as f64 / 1_000_000_f64) * pricing.input_per_million
-        + (cache_creation as f64 / 1_000_000_f64) * pricing.cache_create_per_million
-        + (cache_read as f64 / 1_000_000_f64) * pricing.cache_read_per_million;
-    let output_cost = (output as f64 / 1_000_000_f64) * pricing.output_per_million;
-    input_cost + output_cost
+    let mut rows: Vec<Row> = map.into_values().map(|acc| acc.finish()).collect();
+    sort_rows(&mut rows, order);
+    let totals = calculate_totals(&rows);
+    let model_breakdowns = if breakdown {
+        aggregate_models_from_rows(&rows)
+    } else {
+        Vec::new()
+    };
+    let unpriced_models = collect_unpriced_models(rows.iter().flat_map(|row| &row.model_breakdowns));
+    Ok(JsonReport {
+        kind: "weekly",
+        timezone: tz.name().to_string(),
+        locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
+        since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
+        until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
+        rows,
+        totals,
+        model_breakdowns,
+        unpriced_models,
+        duplicates_removed: 0,
+    })
 }
 
-fn build_daily_report(
+/// Buckets by calendar month, or by billing cycles starting on `cycle_start_day`
+/// (keyed by their date range) when that is not the 1st.
+#[allow(clippy::too_many_arguments)]
+fn build_monthly_report(
     events: &[UsageEvent],
     tz: &Tz,
     locale: &Locale,
     range: &RangeFilter,
     order: Order,
     instances: bool,
+    cycle_start_day: u32,
     breakdown: bool,
 ) -> Result<JsonReport> {
     let mut map: BTreeMap<(NaiveDate, Option<String>), RowAccumulator> = BTreeMap::new();
 
     for ev in events {
-        if let Some(project) = &range.project {
-            if &ev.project != project {
-                continue;
-            }
+        if !range.includes(ev) {
+            continue;
         }
         let date = ev.timestamp.with_timezone(tz).date_naive();
         if !in_range(date, range.since, range.until) {
             continue;
         }
+        let (cycle_start, cycle_end) = billing_cycle(date, cycle_start_day);
         let key = if instances {
-            (date, Some(ev.project.clone()))
+            (cycle_start, Some(ev.project.clone()))
         } else {
-            (date, None)
+            (cycle_start, None)
+        };
+        let period = if cycle_start_day == 1 {
+            cycle_start.format("%Y-%m").to_string()
+        } else {
+            format!("{}..{}", cycle_start.format("%Y-%m-%d"), cycle_end.format("%Y-%m-%d"))
         };
         let display_key = if let Some(project) = key.1.as_ref() {
-            format!("{} ({})", date.format("%Y-%m-%d"), project)
+            format!("{period} ({project})")
         } else {
-            date.format("%Y-%m-%d").to_string()
+            period
         };
         let entry = map
             .entry(key)
@@ -1359,7 +4011,7 @@ fn build_daily_report(
         entry.add_event(ev);
     }
 
-    let mut rows: Vec<Row> = map.into_iter().map(|(_, acc)| acc.finish()).collect();
+    let mut rows: Vec<Row> = map.into_values().map(|acc| acc.finish()).collect();
     sort_rows(&mut rows, order);
     let totals = calculate_totals(&rows);
     let model_breakdowns = if breakdown {
@@ -1367,276 +4019,995 @@ fn build_daily_report(
     } else {
         Vec::new()
     };
+    let unpriced_models = collect_unpriced_models(rows.iter().flat_map(|row| &row.model_breakdowns));
     Ok(JsonReport {
-        kind: "daily",
+        kind: "monthly",
         timezone: tz.name().to_string(),
         locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
         since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
         until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
         rows,
         totals,
         model_breakdowns,
+        unpriced_models,
+        duplicates_removed: 0,
     })
 }
 
-fn build_weekly_report(
+fn build_session_report(
     events: &[UsageEvent],
     tz: &Tz,
     locale: &Locale,
     range: &RangeFilter,
-    order: Order,
-    instances: bool,
-    start_of_week: WeekStart,
     breakdown: bool,
-) -> Result<JsonReport> {
-    let mut map: BTreeMap<(NaiveDate, Option<String>), RowAccumulator> = BTreeMap::new();
+) -> Result<SessionReport> {
+    let mut map: BTreeMap<String, SessionAccumulator> = BTreeMap::new();
 
     for ev in events {
-        if let Some(project) = &range.project {
-            if &ev.project != project {
-                continue;
-            }
+        if !range.includes(ev) {
+            continue;
         }
         let date = ev.timestamp.with_timezone(tz).date_naive();
         if !in_range(date, range.since, range.until) {
             continue;
         }
-        let week_start = week_start_for_date(date, start_of_week);
-        let key = if instances {
-            (week_start, Some(ev.project.clone()))
+        let entry = map
+            .entry(ev.session_id.clone())
+            .or_insert_with(|| SessionAccumulator::new(ev.session_id.clone(), ev.project.clone()));
+        entry.add_event(ev);
+    }
+
+    let mut rows: Vec<SessionRow> = map.into_values().map(|acc| acc.finish()).collect();
+    rows.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+    let totals = calculate_session_totals(&rows);
+    let model_breakdowns = if breakdown {
+        aggregate_models_from_session_rows(&rows)
+    } else {
+        Vec::new()
+    };
+
+    let unpriced_models = collect_unpriced_models(rows.iter().flat_map(|row| &row.model_breakdowns));
+    Ok(SessionReport {
+        kind: "sessions",
+        timezone: tz.name().to_string(),
+        locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
+        since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
+        until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
+        rows,
+        totals,
+        model_breakdowns,
+        unpriced_models,
+        duplicates_removed: 0,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_blocks_report(
+    events: &[UsageEvent],
+    tz: &Tz,
+    locale: &Locale,
+    range: &RangeFilter,
+    token_limit: u64,
+    recent_days: u32,
+    session_length_hours: u32,
+    breakdown: bool,
+) -> Result<BlocksReport> {
+    let block_length = ChronoDuration::hours(session_length_hours.max(1) as i64);
+
+    // determine cutoff
+    let latest_date = events
+        .iter()
+        .map(|e| e.timestamp.with_timezone(tz).date_naive())
+        .max();
+    let cutoff = latest_date.map(|d| d - ChronoDuration::days(recent_days as i64));
+
+    let mut selected: Vec<&UsageEvent> = Vec::new();
+    let mut errors: Vec<&UsageEvent> = Vec::new();
+    for ev in events {
+        if !range.matches(ev) {
+            continue;
+        }
+        let date = ev.timestamp.with_timezone(tz).date_naive();
+        if let Some(cut) = cutoff
+            && date < cut
+        {
+            continue;
+        }
+        if !in_range(date, range.since, range.until) {
+            continue;
+        }
+        if ev.is_error_only() {
+            errors.push(ev);
         } else {
-            (week_start, None)
-        };
-        let display_key = if let Some(project) = key.1.as_ref() {
-            format!("{} ({})", week_start.format("%Y-%m-%d"), project)
+            selected.push(ev);
+        }
+    }
+    let blocks = group_into_blocks(selected, &errors, tz, session_length_hours);
+
+    let now = Utc::now();
+    let mut rows: Vec<BlockRow> = Vec::new();
+    let mut burn_rate = None;
+    let mut previous_end: Option<DateTime<Tz>> = None;
+    for block in blocks {
+        let start = block.start;
+        let end = start + block_length;
+        if let Some(prev_end) = previous_end
+            && start > prev_end
+        {
+            rows.push(gap_block_row(prev_end, start));
+        }
+        previous_end = Some(end);
+
+        let is_active = now < end.with_timezone(&Utc) && now - block.last_activity < block_length;
+        if is_active {
+            burn_rate = Some(project_burn_rate(&block, end, token_limit, now, tz));
+        }
+        let row = block.acc.finish();
+        let percent = if token_limit > 0 {
+            Some((row.total_tokens as f64 / token_limit as f64) * 100.0)
         } else {
-            week_start.format("%Y-%m-%d").to_string()
+            None
+        };
+        rows.push(BlockRow {
+            block_start: start.to_rfc3339(),
+            block_end: end.to_rfc3339(),
+            is_gap: false,
+            is_active,
+            last_activity: Some(block.last_activity.with_timezone(tz).to_rfc3339()),
+            elapsed_minutes: is_active.then(|| (now - start.with_timezone(&Utc)).num_minutes()),
+            remaining_minutes: is_active.then(|| (end.with_timezone(&Utc) - now).num_minutes()),
+            input_tokens: row.input_tokens,
+            output_tokens: row.output_tokens,
+            cache_creation_tokens: row.cache_creation_tokens,
+            cache_read_tokens: row.cache_read_tokens,
+            total_tokens: row.total_tokens,
+            cost_usd: row.cost_usd,
+            costs: row.costs,
+            converted_cost: row.converted_cost,
+            long_context_requests: row.long_context_requests,
+            sidechain: row.sidechain,
+            errors: (block.errors.total > 0).then_some(block.errors),
+            limit_resets_at: block.limit_resets_at.map(|at| at.with_timezone(tz).to_rfc3339()),
+            percent_of_limit: percent,
+            models: row.models,
+            projects: row.projects,
+            model_breakdowns: row.model_breakdowns,
+        });
+    }
+
+    let totals = calculate_block_totals(&rows);
+    let model_breakdowns = if breakdown {
+        aggregate_models_from_blocks(&rows)
+    } else {
+        Vec::new()
+    };
+
+    let unpriced_models = collect_unpriced_models(rows.iter().flat_map(|row| &row.model_breakdowns));
+    Ok(BlocksReport {
+        kind: "blocks",
+        timezone: tz.name().to_string(),
+        locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
+        recent_days,
+        token_limit: Some(token_limit),
+        rows,
+        totals,
+        burn_rate,
+        model_breakdowns,
+        unpriced_models,
+        duplicates_removed: 0,
+    })
+}
+
+struct ActivityBlock<'a> {
+    start: DateTime<Tz>,
+    last_activity: DateTime<Utc>,
+    events: Vec<&'a UsageEvent>,
+    acc: RowAccumulator,
+    errors: ErrorCounts,
+    first_limit_hit: Option<DateTime<Utc>>,
+    limit_resets_at: Option<DateTime<Utc>>,
+}
+
+impl ActivityBlock<'_> {
+    fn add_error(&mut self, at: DateTime<Utc>, error: &ApiError, tz: &Tz) {
+        self.errors.add(error.kind);
+        if error.kind == ApiErrorKind::UsageLimit {
+            self.first_limit_hit = self.first_limit_hit.or(Some(at));
+        }
+        self.limit_resets_at = error.resets_at(at, tz).or(self.limit_resets_at);
+    }
+}
+
+/// A block opens at the hour of the first entry after the previous block expired,
+/// mirroring how Claude's usage windows start on first activity. Error lines
+/// without usage never open a block; they count towards the block they fall in.
+fn group_into_blocks<'a>(
+    mut selected: Vec<&'a UsageEvent>,
+    errors: &[&UsageEvent],
+    tz: &Tz,
+    session_length_hours: u32,
+) -> Vec<ActivityBlock<'a>> {
+    let block_length = ChronoDuration::hours(session_length_hours.max(1) as i64);
+    selected.sort_by_key(|ev| ev.timestamp);
+    let mut blocks: Vec<ActivityBlock> = Vec::new();
+    for ev in selected {
+        let local_dt = ev.timestamp.with_timezone(tz);
+        let expired = blocks
+            .last()
+            .is_none_or(|b| local_dt >= b.start + block_length);
+        if expired {
+            let start = floor_to_hour(local_dt);
+            blocks.push(ActivityBlock {
+                start,
+                last_activity: ev.timestamp,
+                events: Vec::new(),
+                acc: RowAccumulator::new(format_block_range(start, session_length_hours.max(1))),
+                errors: ErrorCounts::default(),
+                first_limit_hit: None,
+                limit_resets_at: None,
+            });
+        }
+        let block = blocks.last_mut().expect("block opened above");
+        block.last_activity = ev.timestamp;
+        block.events.push(ev);
+        block.acc.add_event(ev);
+        if let Some(error) = &ev.api_error {
+            block.add_error(ev.timestamp, error, tz);
+        }
+    }
+    let mut errors = errors.to_vec();
+    errors.sort_by_key(|ev| ev.timestamp);
+    for ev in errors {
+        let local_dt = ev.timestamp.with_timezone(tz);
+        if let Some(error) = &ev.api_error
+            && let Some(block) = blocks
+                .iter_mut()
+                .find(|b| b.start <= local_dt && local_dt < b.start + block_length)
+        {
+            block.add_error(ev.timestamp, error, tz);
+        }
+    }
+    blocks
+}
+
+/// Rates over the last `BURN_RATE_WINDOW_MINUTES` of the block, extrapolated to its end.
+fn project_burn_rate(
+    block: &ActivityBlock,
+    end: DateTime<Tz>,
+    token_limit: u64,
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> BurnRate {
+    let window_start = (now - ChronoDuration::minutes(BURN_RATE_WINDOW_MINUTES))
+        .max(block.start.with_timezone(&Utc));
+    let window_minutes = (now - window_start).num_minutes().max(1);
+    let (window_tokens, window_cost) = block
+        .events
+        .iter()
+        .filter(|ev| ev.timestamp >= window_start)
+        .fold((0u64, Money::ZERO), |(tokens, cost), ev| {
+            (tokens + event_total_tokens(ev), cost + ev.cost_usd)
+        });
+    let (block_tokens, block_cost) = block.events.iter().fold((0u64, Money::ZERO), |(tokens, cost), ev| {
+        (tokens + event_total_tokens(ev), cost + ev.cost_usd)
+    });
+
+    let tokens_per_minute = window_tokens as f64 / window_minutes as f64;
+    let cost_per_hour = window_cost.scaled(60.0 / window_minutes as f64);
+    let remaining_minutes = (end.with_timezone(&Utc) - now).num_minutes().max(0) as f64;
+    let limit_reached_at = if token_limit > block_tokens && tokens_per_minute > 0.0 {
+        let minutes = ((token_limit - block_tokens) as f64 / tokens_per_minute).ceil();
+        (minutes <= remaining_minutes)
+            .then(|| {
+                (now + ChronoDuration::minutes(minutes as i64))
+                    .with_timezone(tz)
+                    .to_rfc3339_opts(SecondsFormat::Secs, false)
+            })
+    } else {
+        None
+    };
+
+    BurnRate {
+        window_minutes,
+        tokens_per_minute,
+        cost_per_hour,
+        projected_total_tokens: block_tokens + (tokens_per_minute * remaining_minutes).round() as u64,
+        projected_cost_usd: block_cost + cost_per_hour.scaled(remaining_minutes / 60.0),
+        limit_reached_at,
+    }
+}
+
+fn event_total_tokens(ev: &UsageEvent) -> u64 {
+    ev.input_tokens + ev.output_tokens + ev.cache_creation_tokens + ev.cache_read_tokens
+}
+
+/// Steps back to the top of the local hour. Subtracting on the instant keeps times in a
+/// repeated DST hour in the right occurrence, where rebuilding the local time would not.
+fn floor_to_hour(dt: DateTime<Tz>) -> DateTime<Tz> {
+    dt - ChronoDuration::minutes(dt.minute() as i64)
+        - ChronoDuration::seconds(dt.second() as i64)
+        - ChronoDuration::nanoseconds(dt.nanosecond() as i64)
+}
+
+fn gap_block_row(start: DateTime<Tz>, end: DateTime<Tz>) -> BlockRow {
+    BlockRow {
+        block_start: start.to_rfc3339(),
+        block_end: end.to_rfc3339(),
+        is_gap: true,
+        is_active: false,
+        last_activity: None,
+        elapsed_minutes: None,
+        remaining_minutes: None,
+        input_tokens: 0,
+        output_tokens: 0,
+        cache_creation_tokens: 0,
+        cache_read_tokens: 0,
+        total_tokens: 0,
+        cost_usd: Money::ZERO,
+        costs: CostBreakdown::default(),
+        converted_cost: None,
+        long_context_requests: 0,
+        sidechain: None,
+        errors: None,
+        limit_resets_at: None,
+        percent_of_limit: None,
+        models: BTreeSet::new(),
+        projects: BTreeSet::new(),
+        model_breakdowns: Vec::new(),
+    }
+}
+
+impl AuditRow {
+    fn new(key: String) -> Self {
+        AuditRow {
+            key,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, logged: Option<Money>, calculated: Money) {
+        match logged {
+            Some(logged) => {
+                self.events += 1;
+                self.logged_cost_usd += logged;
+                self.calculated_cost_usd += calculated;
+            }
+            None => self.missing_cost_usd_events += 1,
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.delta_usd = self.logged_cost_usd - self.calculated_cost_usd;
+        self.delta_percent = self
+            .delta_usd
+            .ratio(self.calculated_cost_usd)
+            .map(|ratio| ratio * 100.0);
+        self
+    }
+
+    fn exceeds(&self, tolerance_percent: f64) -> bool {
+        match self.delta_percent {
+            Some(percent) => percent.abs() > tolerance_percent,
+            // Nothing to compare against: any logged cost is a discrepancy
+            None => self.logged_cost_usd > Money::ZERO,
+        }
+    }
+}
+
+/// Prices every event from the pricing table regardless of the cost mode and
+/// compares the result with the logged `costUSD`.
+fn build_audit_report(
+    events: &[UsageEvent],
+    tz: &Tz,
+    range: &RangeFilter,
+    tolerance_percent: f64,
+) -> AuditReport {
+    let mut days: BTreeMap<NaiveDate, AuditRow> = BTreeMap::new();
+    let mut models: BTreeMap<String, AuditRow> = BTreeMap::new();
+    let mut per_model: BTreeMap<String, ModelAccumulator> = BTreeMap::new();
+    let mut totals = AuditRow::new("Total".into());
+    let mut missing_cost_usd = MissingCost::default();
+    let mut missing: Vec<(&UsageEvent, Money)> = Vec::new();
+    for ev in events {
+        if !range.includes(ev) {
+            continue;
+        }
+        let date = ev.timestamp.with_timezone(tz).date_naive();
+        if !in_range(date, range.since, range.until) {
+            continue;
+        }
+        let calculated = calculate_cost(
+            ev.model.as_deref(),
+            ev.timestamp,
+            ev.input_tokens,
+            ev.output_tokens,
+            ev.cache_creation_5m_tokens,
+            ev.cache_creation_1h_tokens,
+            ev.cache_read_tokens,
+        )
+        .map_or(Money::ZERO, |(costs, _)| costs.total());
+        let logged = ev.logged_cost_usd.map(Money::from_usd);
+        let model = ev.model.clone().unwrap_or_else(|| "unknown".into());
+        days.entry(date)
+            .or_insert_with(|| AuditRow::new(date.format("%Y-%m-%d").to_string()))
+            .add(logged, calculated);
+        models
+            .entry(model.clone())
+            .or_insert_with(|| AuditRow::new(model.clone()))
+            .add(logged, calculated);
+        per_model.entry(model).or_default().add(ev);
+        totals.add(logged, calculated);
+        if ev.logged_cost_usd.is_none() {
+            missing_cost_usd.events += 1;
+            missing_cost_usd.calculated_cost_usd += calculated;
+            missing.push((ev, calculated));
+        }
+    }
+    missing.sort_by_key(|(ev, _)| ev.timestamp);
+    missing_cost_usd.sample = missing
+        .into_iter()
+        .take(MISSING_COST_SAMPLE_SIZE)
+        .map(|(ev, calculated)| MissingCostEvent {
+            timestamp: ev.timestamp.with_timezone(tz).to_rfc3339(),
+            session_id: ev.session_id.clone(),
+            project: ev.project.clone(),
+            model: ev.model.clone(),
+            calculated_cost_usd: calculated,
+        })
+        .collect();
+
+    let models: Vec<AuditRow> = models.into_values().map(AuditRow::finish).collect();
+    let over_tolerance = models
+        .iter()
+        .filter(|row| row.exceeds(tolerance_percent))
+        .map(|row| row.key.clone())
+        .collect();
+    let breakdowns: Vec<ModelBreakdown> = per_model
+        .into_iter()
+        .map(|(model, acc)| acc.finish(model))
+        .collect();
+    AuditReport {
+        kind: "audit",
+        timezone: tz.name().to_string(),
+        since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
+        until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
+        tolerance_percent,
+        days: days.into_values().map(AuditRow::finish).collect(),
+        models,
+        totals: totals.finish(),
+        missing_cost_usd,
+        over_tolerance,
+        unpriced_models: collect_unpriced_models(&breakdowns),
+        duplicates_removed: 0,
+    }
+}
+
+/// Groups usage into billing cycles and compares each cycle's API-equivalent
+/// cost with the plan fee.
+fn build_plan_report(
+    events: &[UsageEvent],
+    tz: &Tz,
+    range: &RangeFilter,
+    plan: PlanTier,
+    monthly_fee: Money,
+    renewal_day: u32,
+) -> PlanReport {
+    let mut sorted: Vec<&UsageEvent> = events
+        .iter()
+        .filter(|ev| range.includes(ev))
+        .filter(|ev| {
+            in_range(ev.timestamp.with_timezone(tz).date_naive(), range.since, range.until)
+        })
+        .collect();
+    sorted.sort_by_key(|ev| ev.timestamp);
+
+    let today = Utc::now().with_timezone(tz).date_naive();
+    let new_cycle = |start: NaiveDate, end: NaiveDate| PlanCycle {
+        cycle_start: start.format("%Y-%m-%d").to_string(),
+        cycle_end: end.format("%Y-%m-%d").to_string(),
+        is_current: (start..=end).contains(&today),
+        plan_fee_usd: monthly_fee,
+        ..Default::default()
+    };
+    // The fee is due every cycle up to today, so cycles without usage still count
+    let first = range
+        .since
+        .or_else(|| sorted.first().map(|ev| ev.timestamp.with_timezone(tz).date_naive()));
+    let last = range.until.map_or(today, |until| until.min(today));
+    let mut cycles: BTreeMap<NaiveDate, PlanCycle> = BTreeMap::new();
+    if let Some(mut day) = first {
+        while day <= last {
+            let (start, end) = billing_cycle(day, renewal_day);
+            cycles.insert(start, new_cycle(start, end));
+            day = end + ChronoDuration::days(1);
+        }
+    }
+    let mut per_model: BTreeMap<String, ModelAccumulator> = BTreeMap::new();
+    for ev in sorted {
+        let date = ev.timestamp.with_timezone(tz).date_naive();
+        let (start, end) = billing_cycle(date, renewal_day);
+        let cycle = cycles.entry(start).or_insert_with(|| new_cycle(start, end));
+        cycle.total_tokens += event_total_tokens(ev);
+        cycle.api_cost_usd += ev.cost_usd;
+        if cycle.break_even_date.is_none()
+            && monthly_fee > Money::ZERO
+            && cycle.api_cost_usd >= monthly_fee
+        {
+            cycle.break_even_date = Some(date.format("%Y-%m-%d").to_string());
+        }
+        if let Some(model) = &ev.model {
+            per_model.entry(model.clone()).or_default().add(ev);
+        }
+    }
+
+    let cycles: Vec<PlanCycle> = cycles.into_values().map(PlanCycle::finish).collect();
+    let totals = PlanCycle {
+        cycle_start: cycles.first().map(|c| c.cycle_start.clone()).unwrap_or_default(),
+        cycle_end: cycles.last().map(|c| c.cycle_end.clone()).unwrap_or_default(),
+        total_tokens: cycles.iter().map(|c| c.total_tokens).sum(),
+        api_cost_usd: cycles.iter().map(|c| c.api_cost_usd).sum(),
+        plan_fee_usd: cycles.iter().map(|c| c.plan_fee_usd).sum(),
+        ..Default::default()
+    }
+    .finish();
+    let breakdowns: Vec<ModelBreakdown> = per_model
+        .into_iter()
+        .map(|(model, acc)| acc.finish(model))
+        .collect();
+    PlanReport {
+        kind: "plan",
+        timezone: tz.name().to_string(),
+        plan,
+        monthly_fee_usd: monthly_fee,
+        renewal_day,
+        cycles,
+        totals,
+        unpriced_models: collect_unpriced_models(&breakdowns),
+        duplicates_removed: 0,
+    }
+}
+
+/// Top of the git checkout containing `cwd` while it exists on disk, else `cwd` itself.
+fn repository_root(cwd: &Path) -> &Path {
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(cwd)
+}
+
+#[derive(Default)]
+struct BranchAccumulator<'a> {
+    usage: RowAccumulator,
+    sessions: HashSet<&'a str>,
+    last_activity: DateTime<Utc>,
+}
+
+/// Error and limit messages over time, counted per day and per usage block.
+fn build_errors_report(
+    events: &[UsageEvent],
+    tz: &Tz,
+    range: &RangeFilter,
+    session_length_hours: u32,
+) -> ErrorsReport {
+    let block_length = ChronoDuration::hours(session_length_hours.max(1) as i64);
+    let (mut errors, selected): (Vec<&UsageEvent>, Vec<&UsageEvent>) = events
+        .iter()
+        .filter(|ev| range.matches(ev))
+        .filter(|ev| {
+            let date = ev.timestamp.with_timezone(tz).date_naive();
+            in_range(date, range.since, range.until)
+        })
+        .partition(|ev| ev.is_error_only());
+    let mut per_model: BTreeMap<String, ModelAccumulator> = BTreeMap::new();
+    for ev in &selected {
+        let model = ev.model.clone().unwrap_or_else(|| "unknown".into());
+        per_model.entry(model).or_default().add(ev);
+    }
+    let blocks = group_into_blocks(selected.clone(), &errors, tz, session_length_hours);
+    errors.extend(selected.into_iter().filter(|ev| ev.api_error.is_some()));
+    errors.sort_by_key(|ev| ev.timestamp);
+
+    let mut entries: Vec<ErrorEntry> = Vec::new();
+    let mut days: BTreeMap<NaiveDate, ErrorCounts> = BTreeMap::new();
+    let mut totals = ErrorCounts::default();
+    for ev in errors {
+        let Some(error) = &ev.api_error else {
+            continue;
         };
-        let entry = map
-            .entry(key)
-            .or_insert_with(|| RowAccumulator::new(display_key));
-        entry.add_event(ev);
+        let local = ev.timestamp.with_timezone(tz);
+        days.entry(local.date_naive()).or_default().add(error.kind);
+        totals.add(error.kind);
+        entries.push(ErrorEntry {
+            timestamp: local.to_rfc3339(),
+            kind: error.kind,
+            session_id: ev.session_id.clone(),
+            project: ev.project.clone(),
+            message: error.message.clone(),
+            resets_at: error
+                .resets_at(ev.timestamp, tz)
+                .map(|at| at.with_timezone(tz).to_rfc3339()),
+        });
     }
+    let blocks = blocks
+        .into_iter()
+        .filter(|block| block.errors.total > 0)
+        .map(|block| {
+            let row = block.acc.finish();
+            ErrorBlock {
+                block_start: block.start.to_rfc3339(),
+                block_end: (block.start + block_length).to_rfc3339(),
+                counts: block.errors,
+                first_limit_hit: block
+                    .first_limit_hit
+                    .map(|at| at.with_timezone(tz).to_rfc3339()),
+                limit_resets_at: block
+                    .limit_resets_at
+                    .map(|at| at.with_timezone(tz).to_rfc3339()),
+                total_tokens: row.total_tokens,
+                cost_usd: row.cost_usd,
+            }
+        })
+        .collect();
 
-    let mut rows: Vec<Row> = map.into_iter().map(|(_, acc)| acc.finish()).collect();
-    sort_rows(&mut rows, order);
-    let totals = calculate_totals(&rows);
-    let model_breakdowns = if breakdown {
-        aggregate_models_from_rows(&rows)
-    } else {
-        Vec::new()
-    };
-    Ok(JsonReport {
-        kind: "weekly",
+    ErrorsReport {
+        kind: "errors",
         timezone: tz.name().to_string(),
-        locale: locale_to_string(locale),
         since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
         until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
-        rows,
+        events: entries,
+        days: days
+            .into_iter()
+            .map(|(date, counts)| ErrorDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                counts,
+            })
+            .collect(),
+        blocks,
         totals,
-        model_breakdowns,
-    })
+        unpriced_models: collect_unpriced_models(
+            &per_model
+                .into_iter()
+                .map(|(model, acc)| acc.finish(model))
+                .collect::<Vec<_>>(),
+        ),
+        duplicates_removed: 0,
+    }
 }
 
-fn build_monthly_report(
+/// One turn's calls to a single tool and their share of the turn's usage.
+#[derive(Default)]
+struct ToolShare {
+    calls: u64,
+    tokens: u64,
+    cost: Money,
+    converted: Option<Money>,
+}
+
+#[derive(Default)]
+struct ToolAccumulator<'a> {
+    tools: BTreeSet<&'a str>,
+    invocations: u64,
+    turns: u64,
+    sessions: HashSet<&'a str>,
+    total_tokens: u64,
+    cost_usd: Money,
+    converted_cost: Option<Money>,
+    by_day: BTreeMap<String, u64>,
+    by_project: BTreeMap<&'a str, u64>,
+    by_session: BTreeMap<&'a str, u64>,
+}
+
+impl<'a> ToolAccumulator<'a> {
+    fn add(&mut self, ev: &'a UsageEvent, day: &str, share: &ToolShare) {
+        self.invocations += share.calls;
+        self.turns += 1;
+        self.sessions.insert(&ev.session_id);
+        self.total_tokens += share.tokens;
+        self.cost_usd += share.cost;
+        add_converted(&mut self.converted_cost, share.converted);
+        *self.by_day.entry(day.to_string()).or_default() += share.calls;
+        *self.by_project.entry(&ev.project).or_default() += share.calls;
+        *self.by_session.entry(&ev.session_id).or_default() += share.calls;
+    }
+
+    fn finish(self) -> ToolUsage {
+        let counts = |map: BTreeMap<&str, u64>| {
+            map.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+        };
+        ToolUsage {
+            invocations: self.invocations,
+            turns: self.turns,
+            sessions: self.sessions.len(),
+            total_tokens: self.total_tokens,
+            cost_usd: self.cost_usd,
+            converted_cost: self.converted_cost,
+            by_day: self.by_day,
+            by_project: counts(self.by_project),
+            by_session: counts(self.by_session),
+        }
+    }
+}
+
+/// `server` for MCP tools, which are named `mcp__server__tool`.
+fn mcp_server(tool: &str) -> Option<&str> {
+    let (server, _) = tool.strip_prefix("mcp__")?.split_once("__")?;
+    Some(server)
+}
+
+/// Share `index` of `total` split into `parts`; the first share takes the remainder.
+fn even_share(total: i64, parts: i64, index: usize) -> i64 {
+    total / parts + if index == 0 { total % parts } else { 0 }
+}
+
+/// Counts tool calls and splits each turn's tokens and cost across the calls it made,
+/// most expensive tool first for `Order::Desc`.
+fn build_tools_report(
     events: &[UsageEvent],
     tz: &Tz,
-    locale: &Locale,
     range: &RangeFilter,
     order: Order,
-    instances: bool,
-    breakdown: bool,
-) -> Result<JsonReport> {
-    let mut map: BTreeMap<((i32, u32), Option<String>), RowAccumulator> = BTreeMap::new();
-
+) -> ToolsReport {
+    let mut tools: BTreeMap<&str, ToolAccumulator> = BTreeMap::new();
+    let mut servers: BTreeMap<&str, ToolAccumulator> = BTreeMap::new();
+    let mut totals = ToolTotals::default();
+    let mut without_tools = ToolTotals::default();
+    let mut per_model: BTreeMap<String, ModelAccumulator> = BTreeMap::new();
     for ev in events {
-        if let Some(project) = &range.project {
-            if &ev.project != project {
-                continue;
-            }
+        if !range.includes(ev) {
+            continue;
         }
         let date = ev.timestamp.with_timezone(tz).date_naive();
         if !in_range(date, range.since, range.until) {
             continue;
         }
-        let key_base = (date.year(), date.month());
-        let key = if instances {
-            (key_base, Some(ev.project.clone()))
+        let model = ev.model.clone().unwrap_or_else(|| "unknown".into());
+        per_model.entry(model).or_default().add(ev);
+        let tokens = event_total_tokens(ev);
+        let summary = if ev.tool_uses.is_empty() {
+            &mut without_tools
         } else {
-            (key_base, None)
+            &mut totals
         };
-        let display_key = if let Some(project) = key.1.as_ref() {
-            format!("{:04}-{:02} ({})", date.year(), date.month(), project)
-        } else {
-            format!("{:04}-{:02}", date.year(), date.month())
-        };
-        let entry = map
-            .entry(key)
-            .or_insert_with(|| RowAccumulator::new(display_key));
-        entry.add_event(ev);
+        summary.invocations += ev.tool_uses.len() as u64;
+        summary.turns += 1;
+        summary.total_tokens += tokens;
+        summary.cost_usd += ev.cost_usd;
+        add_converted(&mut summary.converted_cost, ev.converted_cost);
+        if ev.tool_uses.is_empty() {
+            continue;
+        }
+
+        let parts = ev.tool_uses.len() as i64;
+        let mut shares: BTreeMap<&str, ToolShare> = BTreeMap::new();
+        for (index, tool) in ev.tool_uses.iter().enumerate() {
+            let share = shares.entry(tool.name.as_str()).or_default();
+            share.calls += 1;
+            share.tokens += even_share(tokens as i64, parts, index) as u64;
+            share.cost += Money(even_share(ev.cost_usd.0, parts, index));
+            let converted = ev.converted_cost.map(|c| Money(even_share(c.0, parts, index)));
+            add_converted(&mut share.converted, converted);
+        }
+        let day = date.format("%Y-%m-%d").to_string();
+        let mut server_shares: BTreeMap<&str, ToolShare> = BTreeMap::new();
+        for (name, share) in &shares {
+            tools.entry(name).or_default().add(ev, &day, share);
+            if let Some(server) = mcp_server(name) {
+                servers.entry(server).or_default().tools.insert(name);
+                let total = server_shares.entry(server).or_default();
+                total.calls += share.calls;
+                total.tokens += share.tokens;
+                total.cost += share.cost;
+                add_converted(&mut total.converted, share.converted);
+            }
+        }
+        for (server, share) in &server_shares {
+            servers.entry(server).or_default().add(ev, &day, share);
+        }
     }
 
-    let mut rows: Vec<Row> = map.into_iter().map(|(_, acc)| acc.finish()).collect();
-    sort_rows(&mut rows, order);
-    let totals = calculate_totals(&rows);
-    let model_breakdowns = if breakdown {
-        aggregate_models_from_rows(&rows)
-    } else {
-        Vec::new()
+    let by_cost = |a: &ToolUsage, b: &ToolUsage| match order {
+        Order::Asc => a.cost_usd.cmp(&b.cost_usd),
+        Order::Desc => b.cost_usd.cmp(&a.cost_usd),
     };
-    Ok(JsonReport {
-        kind: "monthly",
+    let mut rows: Vec<ToolRow> = tools
+        .into_iter()
+        .map(|(tool, acc)| ToolRow {
+            tool: tool.to_string(),
+            mcp_server: mcp_server(tool).map(str::to_string),
+            usage: acc.finish(),
+        })
+        .collect();
+    rows.sort_by(|a, b| by_cost(&a.usage, &b.usage));
+    let mut mcp_servers: Vec<McpServerRow> = servers
+        .into_iter()
+        .map(|(server, acc)| McpServerRow {
+            server: server.to_string(),
+            tools: acc.tools.iter().map(|t| t.to_string()).collect(),
+            usage: acc.finish(),
+        })
+        .collect();
+    mcp_servers.sort_by(|a, b| by_cost(&a.usage, &b.usage));
+    ToolsReport {
+        kind: "tools",
         timezone: tz.name().to_string(),
-        locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
         since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
         until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
         rows,
+        mcp_servers,
         totals,
-        model_breakdowns,
-    })
+        without_tools,
+        unpriced_models: collect_unpriced_models(
+            &per_model
+                .into_iter()
+                .map(|(model, acc)| acc.finish(model))
+                .collect::<Vec<_>>(),
+        ),
+        duplicates_removed: 0,
+    }
 }
 
-fn build_session_report(
+#[derive(Default)]
+struct VersionAccumulator<'a> {
+    usage: RowAccumulator,
+    sessions: HashSet<&'a str>,
+    days: BTreeSet<NaiveDate>,
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+/// Cache reads as a share of all input the model saw.
+fn cache_hit_ratio(input: u64, cache_creation: u64, cache_read: u64) -> Option<f64> {
+    let all_input = input + cache_creation + cache_read;
+    (all_input > 0).then(|| cache_read as f64 / all_input as f64)
+}
+
+/// Groups usage by Claude Code version, newest release first for `Order::Desc`;
+/// versions are ordered by when they were first seen.
+fn build_versions_report(
     events: &[UsageEvent],
     tz: &Tz,
-    locale: &Locale,
     range: &RangeFilter,
-    breakdown: bool,
-) -> Result<SessionReport> {
-    let mut map: BTreeMap<String, SessionAccumulator> = BTreeMap::new();
-
+    order: Order,
+) -> VersionsReport {
+    let mut map: HashMap<Option<&str>, VersionAccumulator> = HashMap::new();
     for ev in events {
-        if let Some(project) = &range.project {
-            if &ev.project != project {
-                continue;
-            }
+        if !range.includes(ev) {
+            continue;
         }
         let date = ev.timestamp.with_timezone(tz).date_naive();
         if !in_range(date, range.since, range.until) {
             continue;
         }
-        let entry = map
-            .entry(ev.session_id.clone())
-            .or_insert_with(|| SessionAccumulator::new(ev.session_id.clone(), ev.project.clone()));
-        entry.add_event(ev);
+        let entry = map.entry(ev.version.as_deref()).or_default();
+        entry.usage.add_event(ev);
+        entry.sessions.insert(ev.session_id.as_str());
+        entry.days.insert(date);
+        entry.first_seen = Some(entry.first_seen.map_or(ev.timestamp, |t| t.min(ev.timestamp)));
+        entry.last_seen = Some(entry.last_seen.map_or(ev.timestamp, |t| t.max(ev.timestamp)));
     }
 
-    let mut rows: Vec<SessionRow> = map.into_iter().map(|(_, acc)| acc.finish()).collect();
-    rows.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
-    let totals = calculate_session_totals(&rows);
-    let model_breakdowns = if breakdown {
-        aggregate_models_from_session_rows(&rows)
-    } else {
-        Vec::new()
-    };
-
-    Ok(SessionReport {
-        kind: "sessions",
+    let mut versions: Vec<(Option<&str>, VersionAccumulator)> = map.into_iter().collect();
+    versions.sort_by_key(|(version, entry)| (entry.first_seen, *version));
+    let mut rows = Vec::new();
+    let mut finished = Vec::new();
+    let mut previous_ratio = None;
+    for (version, entry) in versions {
+        let row = entry.usage.finish();
+        let ratio =
+            cache_hit_ratio(row.input_tokens, row.cache_creation_tokens, row.cache_read_tokens);
+        let seen = |at: Option<DateTime<Utc>>| {
+            at.map(|at| at.with_timezone(tz).to_rfc3339()).unwrap_or_default()
+        };
+        rows.push(VersionRow {
+            version: version.map(str::to_string),
+            first_seen: seen(entry.first_seen),
+            last_seen: seen(entry.last_seen),
+            days: entry.days.len(),
+            sessions: entry.sessions.len(),
+            input_tokens: row.input_tokens,
+            output_tokens: row.output_tokens,
+            cache_creation_tokens: row.cache_creation_tokens,
+            cache_read_tokens: row.cache_read_tokens,
+            total_tokens: row.total_tokens,
+            cost_usd: row.cost_usd,
+            costs: row.costs,
+            converted_cost: row.converted_cost,
+            cache_hit_ratio: ratio,
+            cache_hit_ratio_change: ratio.zip(previous_ratio).map(|(now, before)| now - before),
+            models: row.models.clone(),
+        });
+        previous_ratio = ratio.or(previous_ratio);
+        finished.push(row);
+    }
+    if order == Order::Desc {
+        rows.reverse();
+    }
+    VersionsReport {
+        kind: "versions",
         timezone: tz.name().to_string(),
-        locale: locale_to_string(locale),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
         since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
         until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
         rows,
-        totals,
-        model_breakdowns,
-    })
+        totals: calculate_totals(&finished),
+        unpriced_models: collect_unpriced_models(
+            finished.iter().flat_map(|row| &row.model_breakdowns),
+        ),
+        duplicates_removed: 0,
+    }
 }
 
-fn build_blocks_report(
+/// Groups usage by repository and branch, most expensive first for `Order::Desc`.
+fn build_branches_report(
     events: &[UsageEvent],
     tz: &Tz,
-    locale: &Locale,
     range: &RangeFilter,
-    token_limit: u64,
-    recent_days: u32,
-    session_length_hours: u32,
-    breakdown: bool,
-) -> Result<BlocksReport> {
-    let mut map: BTreeMap<DateTime<Tz>, RowAccumulator> = BTreeMap::new();
-
-    // determine cutoff
-    let latest_date = events
-        .iter()
-        .map(|e| e.timestamp.with_timezone(tz).date_naive())
-        .max();
-    let cutoff = latest_date.map(|d| d - ChronoDuration::days(recent_days as i64));
-
+    order: Order,
+) -> BranchReport {
+    let mut repositories: HashMap<&str, String> = HashMap::new();
+    let mut map: BTreeMap<(String, Option<String>), BranchAccumulator> = BTreeMap::new();
     for ev in events {
-        if let Some(project) = &range.project {
-            if &ev.project != project {
-                continue;
-            }
-        }
-        let local_dt = ev.timestamp.with_timezone(tz);
-        let date = local_dt.date_naive();
-        if let Some(cut) = cutoff {
-            if date < cut {
-                continue;
-            }
+        if !range.includes(ev) {
+            continue;
         }
+        let date = ev.timestamp.with_timezone(tz).date_naive();
         if !in_range(date, range.since, range.until) {
             continue;
         }
-        let block_hours = session_length_hours.max(1);
-        let block_start_hour = (local_dt.hour() / block_hours) * block_hours;
-        let block_start = local_dt
-            .with_hour(block_start_hour)
-            .and_then(|d| d.with_minute(0))
-            .and_then(|d| d.with_second(0))
-            .and_then(|d| d.with_nanosecond(0))
-            .expect("valid datetime");
-        let entry = map
-            .entry(block_start)
-            .or_insert_with(|| RowAccumulator::new(format_block_range(block_start, block_hours)));
-        entry.add_event(ev);
+        let repository = match ev.cwd.as_deref() {
+            Some(cwd) => repositories
+                .entry(cwd)
+                .or_insert_with(|| display_project_path(repository_root(Path::new(cwd))))
+                .clone(),
+            None => ev.project.clone(),
+        };
+        let entry = map.entry((repository, ev.git_branch.clone())).or_default();
+        entry.usage.add_event(ev);
+        entry.sessions.insert(ev.session_id.as_str());
+        entry.last_activity = entry.last_activity.max(ev.timestamp);
     }
 
-    let mut rows: Vec<BlockRow> = map
-        .into_iter()
-        .map(|(start, acc)| {
-            let row = acc.finish();
-            let hours = session_length_hours.max(1);
-            let end = start + ChronoDuration::hours(hours as i64);
-            let percent = if token_limit > 0 {
-                Some((row.total_tokens as f64 / token_limit as f64) * 100.0)
-            } else {
-                None
-            };
-            BlockRow {
-                block_start: start.to_rfc3339(),
-                block_end: end.to_rfc3339(),
-                input_tokens: row.input_tokens,
-                output_tokens: row.output_tokens,
-                cache_creation_tokens: row.cache_creation_tokens,
-                cache_read_tokens: row.cache_read_tokens,
-                total_tokens: row.total_tokens,
-                cost_usd: row.cost_usd,
-                percent_of_limit: percent,
-                models: row.models,
-                projects: row.projects,
-                model_breakdowns: row.model_breakdowns,
-            }
-        })
-        .collect();
-    rows.sort_by(|a, b| a.block_start.cmp(&b.block_start));
-
-    let totals = calculate_block_totals(&rows);
-    let model_breakdowns = if breakdown {
-        aggregate_models_from_blocks(&rows)
-    } else {
-        Vec::new()
-    };
-
-    Ok(BlocksReport {
-        kind: "blocks",
+    let mut rows = Vec::new();
+    let mut finished = Vec::new();
+    for ((repository, branch), entry) in map {
+        let row = entry.usage.finish();
+        rows.push(BranchRow {
+            repository,
+            branch,
+            sessions: entry.sessions.len(),
+            last_activity: entry.last_activity.with_timezone(tz).to_rfc3339(),
+            input_tokens: row.input_tokens,
+            output_tokens: row.output_tokens,
+            cache_creation_tokens: row.cache_creation_tokens,
+            cache_read_tokens: row.cache_read_tokens,
+            total_tokens: row.total_tokens,
+            cost_usd: row.cost_usd,
+            costs: row.costs,
+            converted_cost: row.converted_cost,
+            models: row.models.clone(),
+        });
+        finished.push(row);
+    }
+    rows.sort_by(|a, b| match order {
+        Order::Asc => a.cost_usd.cmp(&b.cost_usd),
+        Order::Desc => b.cost_usd.cmp(&a.cost_usd),
+    });
+    BranchReport {
+        kind: "branches",
         timezone: tz.name().to_string(),
-        locale: locale_to_string(locale),
-        recent_days,
-        token_limit: Some(token_limit),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
+        since: range.since.map(|d| d.format("%Y-%m-%d").to_string()),
+        until: range.until.map(|d| d.format("%Y-%m-%d").to_string()),
         rows,
-        totals,
-        model_breakdowns,
-    })
+        totals: calculate_totals(&finished),
+        unpriced_models: collect_unpriced_models(
+            finished.iter().flat_map(|row| &row.model_breakdowns),
+        ),
+        duplicates_removed: 0,
+    }
 }
 
 fn build_statusline_report(
@@ -1645,11 +5016,10 @@ fn build_statusline_report(
     range: &RangeFilter,
 ) -> Result<StatuslineReport> {
     let mut map: BTreeMap<NaiveDate, Totals> = BTreeMap::new();
+    let mut per_model: BTreeMap<NaiveDate, BTreeMap<String, ModelAccumulator>> = BTreeMap::new();
     for ev in events {
-        if let Some(project) = &range.project {
-            if &ev.project != project {
-                continue;
-            }
+        if !range.includes(ev) {
+            continue;
         }
         let date = ev.timestamp.with_timezone(tz).date_naive();
         if !in_range(date, range.since, range.until) {
@@ -1661,7 +5031,11 @@ fn build_statusline_report(
             cache_creation_tokens: 0,
             cache_read_tokens: 0,
             total_tokens: 0,
-            cost_usd: 0.0,
+            cost_usd: Money::ZERO,
+            costs: CostBreakdown::default(),
+            converted_cost: None,
+            long_context_requests: 0,
+            sidechain: None,
         });
         entry.input_tokens += ev.input_tokens;
         entry.output_tokens += ev.output_tokens;
@@ -1670,7 +5044,27 @@ fn build_statusline_report(
         entry.total_tokens +=
             ev.input_tokens + ev.output_tokens + ev.cache_creation_tokens + ev.cache_read_tokens;
         entry.cost_usd += ev.cost_usd;
+        entry.costs += ev.costs;
+        add_converted(&mut entry.converted_cost, ev.converted_cost);
+        entry.long_context_requests += u64::from(ev.long_context);
+        if sidechain_mode() == SidechainMode::Split {
+            entry.sidechain.get_or_insert_default().add(ev);
+        }
+        if let Some(model) = &ev.model {
+            per_model
+                .entry(date)
+                .or_default()
+                .entry(model.clone())
+                .or_default()
+                .add(ev);
+        }
     }
+    let last_day_models: Vec<ModelBreakdown> = map
+        .keys()
+        .next_back()
+        .and_then(|date| per_model.remove(date))
+        .map(|models| models.into_iter().map(|(model, acc)| acc.finish(model)).collect())
+        .unwrap_or_default();
     let (last_date, totals) = if let Some((date, totals)) = map.into_iter().max_by_key(|(d, _)| *d)
     {
         (Some(date.format("%Y-%m-%d").to_string()), totals)
@@ -1683,18 +5077,272 @@ fn build_statusline_report(
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
                 total_tokens: 0,
-                cost_usd: 0.0,
+                cost_usd: Money::ZERO,
+                costs: CostBreakdown::default(),
+                converted_cost: None,
+                long_context_requests: 0,
+                sidechain: None,
             },
         )
     };
 
-    Ok(StatuslineReport {
-        kind: "statusline",
-        timezone: tz.name().to_string(),
-        locale: "en".to_string(),
-        last_date,
-        totals,
-    })
+    Ok(StatuslineReport {
+        kind: "statusline",
+        timezone: tz.name().to_string(),
+        locale: "en".to_string(),
+        currency: CURRENCY.get().map(|c| c.code.clone()),
+        last_date,
+        totals,
+        session: None,
+        unpriced_models: collect_unpriced_models(&last_day_models),
+        duplicates_removed: 0,
+    })
+}
+
+/// The hook payload on stdin, if any. Reading gives up after a short wait so
+/// that cron jobs and scripts with an idle stdin pipe don't hang.
+fn read_statusline_hook() -> Result<Option<StatuslineHook>> {
+    if std::io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        let mut raw = String::new();
+        let result = std::io::stdin()
+            .lock()
+            .take(STATUSLINE_HOOK_MAX_BYTES)
+            .read_to_string(&mut raw)
+            .map(|_| raw);
+        let _ = tx.send(result);
+    });
+    let raw = match rx.recv_timeout(Duration::from_millis(STATUSLINE_HOOK_TIMEOUT_MS)) {
+        Ok(result) => result.context("reading statusline hook from stdin")?,
+        Err(_) => return Ok(None),
+    };
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+    let hook = serde_json::from_str(&raw).context("parsing statusline hook JSON from stdin")?;
+    Ok(Some(hook))
+}
+
+/// Statusline for the session in `hook`: only its transcript is parsed, everything
+/// else comes from the recent-events cache.
+fn build_hook_statusline_report(
+    hook: &StatuslineHook,
+    tz: &Tz,
+    data_dirs: &[PathBuf],
+    use_cache: bool,
+    token_limit: u64,
+    session_length_hours: u32,
+) -> Result<StatuslineReport> {
+    let now = Utc::now();
+    let today = now.with_timezone(tz).date_naive();
+    let block_length = ChronoDuration::hours(session_length_hours.max(1) as i64);
+    let day_start = today
+        .and_hms_opt(0, 0, 0)
+        .and_then(|d| d.and_local_timezone(*tz).earliest())
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or(now - ChronoDuration::days(1));
+    // Today's entries plus enough history to place the active block
+    let since = day_start.min(now - block_length * 2);
+    let transcript_path = hook.transcript_path.as_deref().map(expand_tilde);
+    let (transcript, others) =
+        load_statusline_transcripts(data_dirs, transcript_path.as_deref(), since, use_cache)?;
+    let session_id = hook
+        .session_id
+        .clone()
+        .or_else(|| transcript.first().map(|ev| ev.session_id.clone()));
+
+    // The session's cost counts each of its entries once, even if an earlier session
+    // logged the same one; only the day and block totals dedupe across sessions
+    let mut session_dedup = Deduplicator::default();
+    let transcript = session_dedup.retain_new(transcript);
+    let session_cost_usd = transcript.iter().map(|ev| ev.cost_usd).sum();
+    let transcript_model = transcript.iter().rev().find_map(|ev| ev.model.clone());
+    let mut dedup = Deduplicator::default();
+    let mut events: Vec<UsageEvent> = others
+        .into_iter()
+        .flat_map(|file_events| dedup.retain_new(file_events))
+        .collect();
+    events.extend(
+        dedup.retain_new(transcript.into_iter().filter(|ev| ev.timestamp >= since).collect()),
+    );
+
+    let today_range = RangeFilter {
+        since: Some(today),
+        until: Some(today),
+        project: None,
+        branch: None,
+    };
+    let mut report = build_statusline_report(&events, tz, &today_range)?;
+    let all_range = RangeFilter {
+        since: None,
+        until: None,
+        project: None,
+        branch: None,
+    };
+    let blocks = build_blocks_report(
+        &events,
+        tz,
+        &Locale::en,
+        &all_range,
+        token_limit,
+        DEFAULT_RECENT_DAYS,
+        session_length_hours,
+        false,
+    )?;
+    let active = blocks.rows.iter().find(|row| row.is_active);
+
+    let model = hook
+        .model
+        .as_ref()
+        .and_then(|m| m.display_name.clone().or_else(|| m.id.clone()))
+        .or_else(|| transcript_model.map(|m| format_model_name(&m)));
+    report.last_date = Some(today.format("%Y-%m-%d").to_string());
+    report.session = Some(StatuslineSession {
+        session_id,
+        model,
+        cwd: hook.cwd.clone(),
+        session_cost_usd,
+        today_cost_usd: report.totals.cost_usd,
+        block_cost_usd: active.map(|row| row.cost_usd),
+        block_remaining_minutes: active.and_then(|row| row.remaining_minutes),
+        burn_rate: blocks.burn_rate,
+    });
+    report.duplicates_removed = session_dedup.removed() + dedup.removed();
+    Ok(report)
+}
+
+/// Events of the hook's transcript, plus the events at or after `since` of every other
+/// transcript, one list per file in oldest-first order. Only what was appended since the
+/// last refresh is parsed: the hook's transcript on every call, the other transcripts
+/// written to since `since` once the cached scan is older than the TTL.
+fn load_statusline_transcripts(
+    data_dirs: &[PathBuf],
+    transcript: Option<&Path>,
+    since: DateTime<Utc>,
+    use_cache: bool,
+) -> Result<(Vec<UsageEvent>, Vec<Vec<UsageEvent>>)> {
+    let cache_path = if use_cache {
+        statusline_cache_path()
+    } else {
+        None
+    };
+    let dirs: Vec<PathBuf> = data_dirs
+        .iter()
+        .map(|dir| std::fs::canonicalize(dir).unwrap_or_else(|_| dir.clone()))
+        .collect();
+    let mut cache = cache_path
+        .as_deref()
+        .and_then(|path| load_statusline_cache(path).ok())
+        .filter(|cache| {
+            cache.version == EVENT_INDEX_VERSION && cache.data_dirs == dirs && cache.since <= since
+        })
+        .unwrap_or_else(|| StatuslineCache {
+            version: EVENT_INDEX_VERSION,
+            data_dirs: dirs,
+            since,
+            refreshed_at: DateTime::<Utc>::MIN_UTC,
+            files: HashMap::new(),
+        });
+    let transcript_key =
+        transcript.map(|path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    let session_entry = transcript_key.as_ref().and_then(|key| cache.files.remove(key));
+
+    let mut changed = false;
+    let mut unterminated: HashMap<PathBuf, UsageEvent> = HashMap::new();
+    if Utc::now() - cache.refreshed_at >= ChronoDuration::seconds(STATUSLINE_CACHE_TTL_SECONDS) {
+        // Only transcripts written to since `since` can hold entries from the window
+        let pending: Vec<(PathBuf, PathBuf, Option<IndexedFile>)> = collect_jsonl_files(data_dirs)
+            .into_iter()
+            .filter(|file| {
+                std::fs::metadata(file)
+                    .and_then(|meta| meta.modified())
+                    .map_or(true, |modified| DateTime::<Utc>::from(modified) >= since)
+            })
+            .filter_map(|file| {
+                let key = std::fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+                if transcript_key.as_ref() == Some(&key) {
+                    return None;
+                }
+                let cached = cache.files.remove(&key);
+                Some((key, file, cached))
+            })
+            .collect();
+        let scanned: Vec<(PathBuf, IndexedFile, Option<UsageEvent>)> = pending
+            .into_par_iter()
+            .filter_map(|(key, file, cached)| {
+                let (entry, last, _) = index_file(&file, cached)?;
+                Some((key, entry, last))
+            })
+            .collect();
+        cache.files.clear();
+        for (key, entry, last) in scanned {
+            if let Some(last) = last {
+                unterminated.insert(key.clone(), last);
+            }
+            cache.files.insert(key, entry);
+        }
+        cache.refreshed_at = Utc::now();
+        changed = true;
+    } else {
+        // Costs depend on the current pricing and cost mode, so they are never trusted from disk
+        for ev in cache.files.values_mut().flat_map(|entry| entry.events.iter_mut()) {
+            label_project(ev);
+            price_event(ev);
+        }
+    }
+
+    let mut session_events = Vec::new();
+    if let (Some(path), Some(key)) = (transcript, transcript_key.clone())
+        && let Some((entry, last, indexed)) = index_file(path, session_entry)
+    {
+        changed |= indexed;
+        session_events = entry.events.clone();
+        session_events.extend(last);
+        cache.files.insert(key, entry);
+    }
+
+    if let Some(path) = &cache_path
+        && changed
+    {
+        let contents = serde_json::to_vec(&cache).context("serializing statusline cache")?;
+        if let Err(err) = write_cache_file(path, &contents) {
+            eprintln!("Failed to write statusline cache ({err:#})");
+        }
+    }
+
+    let mut per_file: Vec<(PathBuf, Vec<UsageEvent>)> = cache
+        .files
+        .into_iter()
+        .filter(|(key, _)| transcript_key.as_ref() != Some(key))
+        .map(|(key, entry)| {
+            let mut file_events = entry.events;
+            file_events.extend(unterminated.remove(&key));
+            (key, file_events)
+        })
+        .collect();
+    // Oldest-first, as in `load_events`, so resumed/forked copies are the ones deduped
+    per_file.sort_by_cached_key(|(file, file_events)| {
+        (file_events.iter().map(|e| e.timestamp).min(), file.clone())
+    });
+    let others = per_file
+        .into_iter()
+        .map(|(_, mut file_events)| {
+            file_events.retain(|ev| ev.timestamp >= since);
+            file_events
+        })
+        .collect();
+    Ok((session_events, others))
+}
+
+fn load_statusline_cache(path: &Path) -> Result<StatuslineCache> {
+    let contents = std::fs::read(path)
+        .with_context(|| format!("reading statusline cache {}", path.display()))?;
+    let cache: StatuslineCache = serde_json::from_slice(&contents)
+        .with_context(|| format!("parsing statusline cache {}", path.display()))?;
+    Ok(cache)
 }
 
 fn week_start_for_date(date: NaiveDate, start: WeekStart) -> NaiveDate {
@@ -1707,6 +5355,34 @@ fn week_start_for_date(date: NaiveDate, start: WeekStart) -> NaiveDate {
     date - ChronoDuration::days(diff)
 }
 
+/// `day` of the given month, clamped to the month's last day (e.g. the 31st in February).
+fn clamped_day(year: i32, month: u32, day: u32) -> NaiveDate {
+    (1..=day.clamp(1, 31))
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .expect("every month has a first day")
+}
+
+fn add_months(year: i32, month: u32, delta: i32) -> (i32, u32) {
+    let index = year * 12 + month as i32 - 1 + delta;
+    (index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+}
+
+/// First and last day of the billing cycle containing `date` for cycles that
+/// start on `start_day` of each month.
+fn billing_cycle(date: NaiveDate, start_day: u32) -> (NaiveDate, NaiveDate) {
+    let this_month = clamped_day(date.year(), date.month(), start_day);
+    let (year, month) = if date >= this_month {
+        (date.year(), date.month())
+    } else {
+        add_months(date.year(), date.month(), -1)
+    };
+    let start = clamped_day(year, month, start_day);
+    let (next_year, next_month) = add_months(year, month, 1);
+    let end = clamped_day(next_year, next_month, start_day).pred_opt().expect("valid date");
+    (start, end)
+}
+
 fn parse_date_opt(raw: Option<&str>) -> Result<Option<NaiveDate>> {
     match raw {
         None => Ok(None),
@@ -1719,15 +5395,15 @@ fn parse_date_opt(raw: Option<&str>) -> Result<Option<NaiveDate>> {
 }
 
 fn in_range(date: NaiveDate, since: Option<NaiveDate>, until: Option<NaiveDate>) -> bool {
-    if let Some(s) = since {
-        if date < s {
-            return false;
-        }
+    if let Some(s) = since
+        && date < s
+    {
+        return false;
     }
-    if let Some(u) = until {
-        if date > u {
-            return false;
-        }
+    if let Some(u) = until
+        && date > u
+    {
+        return false;
     }
     true
 }
@@ -1739,7 +5415,11 @@ struct RowAccumulator {
     output_tokens: u64,
     cache_creation_tokens: u64,
     cache_read_tokens: u64,
-    cost_usd: f64,
+    cost_usd: Money,
+    costs: CostBreakdown,
+    converted_cost: Option<Money>,
+    long_context_requests: u64,
+    sidechain: SidechainSplit,
     models: BTreeSet<String>,
     projects: BTreeSet<String>,
     per_model: BTreeMap<String, ModelAccumulator>,
@@ -1759,11 +5439,15 @@ impl RowAccumulator {
         self.cache_creation_tokens += ev.cache_creation_tokens;
         self.cache_read_tokens += ev.cache_read_tokens;
         self.cost_usd += ev.cost_usd;
+        self.costs += ev.costs;
+        add_converted(&mut self.converted_cost, ev.converted_cost);
+        self.long_context_requests += u64::from(ev.long_context);
+        self.sidechain.add(ev);
         if let Some(model) = &ev.model {
             self.models.insert(model.clone());
             self.per_model
                 .entry(model.clone())
-                .or_insert_with(ModelAccumulator::default)
+                .or_default()
                 .add(ev);
         }
         self.projects.insert(ev.project.clone());
@@ -1787,6 +5471,10 @@ impl RowAccumulator {
             cache_read_tokens: self.cache_read_tokens,
             total_tokens,
             cost_usd: self.cost_usd,
+            costs: self.costs,
+            converted_cost: self.converted_cost,
+            long_context_requests: self.long_context_requests,
+            sidechain: self.sidechain.reported(),
             models: self.models,
             projects: self.projects,
             model_breakdowns,
@@ -1799,8 +5487,14 @@ struct ModelAccumulator {
     input_tokens: u64,
     output_tokens: u64,
     cache_creation_tokens: u64,
+    cache_creation_5m_tokens: u64,
+    cache_creation_1h_tokens: u64,
     cache_read_tokens: u64,
-    cost_usd: f64,
+    cost_usd: Money,
+    costs: CostBreakdown,
+    converted_cost: Option<Money>,
+    unpriced_requests: u64,
+    unpriced_tokens: u64,
 }
 
 impl ModelAccumulator {
@@ -1808,8 +5502,16 @@ impl ModelAccumulator {
         self.input_tokens += ev.input_tokens;
         self.output_tokens += ev.output_tokens;
         self.cache_creation_tokens += ev.cache_creation_tokens;
+        self.cache_creation_5m_tokens += ev.cache_creation_5m_tokens;
+        self.cache_creation_1h_tokens += ev.cache_creation_1h_tokens;
         self.cache_read_tokens += ev.cache_read_tokens;
         self.cost_usd += ev.cost_usd;
+        self.costs += ev.costs;
+        add_converted(&mut self.converted_cost, ev.converted_cost);
+        if ev.unpriced {
+            self.unpriced_requests += 1;
+            self.unpriced_tokens += event_total_tokens(ev);
+        }
     }
 
     fn finish(self, model: String) -> ModelBreakdown {
@@ -1818,12 +5520,18 @@ impl ModelAccumulator {
             input_tokens: self.input_tokens,
             output_tokens: self.output_tokens,
             cache_creation_tokens: self.cache_creation_tokens,
+            cache_creation_5m_tokens: self.cache_creation_5m_tokens,
+            cache_creation_1h_tokens: self.cache_creation_1h_tokens,
             cache_read_tokens: self.cache_read_tokens,
             total_tokens: self.input_tokens
                 + self.output_tokens
                 + self.cache_creation_tokens
                 + self.cache_read_tokens,
             cost_usd: self.cost_usd,
+            costs: self.costs,
+            converted_cost: self.converted_cost,
+            unpriced_requests: self.unpriced_requests,
+            unpriced_tokens: self.unpriced_tokens,
         }
     }
 }
@@ -1836,7 +5544,11 @@ struct SessionAccumulator {
     output_tokens: u64,
     cache_creation_tokens: u64,
     cache_read_tokens: u64,
-    cost_usd: f64,
+    cost_usd: Money,
+    costs: CostBreakdown,
+    converted_cost: Option<Money>,
+    long_context_requests: u64,
+    sidechain: SidechainSplit,
     models: BTreeSet<String>,
     per_model: BTreeMap<String, ModelAccumulator>,
 }
@@ -1851,7 +5563,11 @@ impl SessionAccumulator {
             output_tokens: 0,
             cache_creation_tokens: 0,
             cache_read_tokens: 0,
-            cost_usd: 0.0,
+            cost_usd: Money::ZERO,
+            costs: CostBreakdown::default(),
+            converted_cost: None,
+            long_context_requests: 0,
+            sidechain: SidechainSplit::default(),
             models: BTreeSet::new(),
             per_model: BTreeMap::new(),
         }
@@ -1863,6 +5579,10 @@ impl SessionAccumulator {
         self.cache_creation_tokens += ev.cache_creation_tokens;
         self.cache_read_tokens += ev.cache_read_tokens;
         self.cost_usd += ev.cost_usd;
+        self.costs += ev.costs;
+        add_converted(&mut self.converted_cost, ev.converted_cost);
+        self.long_context_requests += u64::from(ev.long_context);
+        self.sidechain.add(ev);
         if ev.timestamp > self.last_activity {
             self.last_activity = ev.timestamp;
         }
@@ -1870,7 +5590,7 @@ impl SessionAccumulator {
             self.models.insert(model.clone());
             self.per_model
                 .entry(model.clone())
-                .or_insert_with(ModelAccumulator::default)
+                .or_default()
                 .add(ev);
         }
         // ensure project reflects latest known (in case of path changes)
@@ -1897,6 +5617,10 @@ impl SessionAccumulator {
             cache_read_tokens: self.cache_read_tokens,
             total_tokens,
             cost_usd: self.cost_usd,
+            costs: self.costs,
+            converted_cost: self.converted_cost,
+            long_context_requests: self.long_context_requests,
+            sidechain: self.sidechain.reported(),
             models: self.models,
             model_breakdowns,
         }
@@ -1910,7 +5634,11 @@ fn calculate_totals(rows: &[Row]) -> Totals {
         cache_creation_tokens: 0,
         cache_read_tokens: 0,
         total_tokens: 0,
-        cost_usd: 0.0,
+        cost_usd: Money::ZERO,
+        costs: CostBreakdown::default(),
+        converted_cost: None,
+        long_context_requests: 0,
+        sidechain: None,
     };
     for row in rows {
         totals.input_tokens += row.input_tokens;
@@ -1919,6 +5647,10 @@ fn calculate_totals(rows: &[Row]) -> Totals {
         totals.cache_read_tokens += row.cache_read_tokens;
         totals.total_tokens += row.total_tokens;
         totals.cost_usd += row.cost_usd;
+        totals.costs += row.costs;
+        add_converted(&mut totals.converted_cost, row.converted_cost);
+        totals.long_context_requests += row.long_context_requests;
+        add_split(&mut totals.sidechain, row.sidechain);
     }
     totals
 }
@@ -1930,7 +5662,11 @@ fn calculate_session_totals(rows: &[SessionRow]) -> Totals {
         cache_creation_tokens: 0,
         cache_read_tokens: 0,
         total_tokens: 0,
-        cost_usd: 0.0,
+        cost_usd: Money::ZERO,
+        costs: CostBreakdown::default(),
+        converted_cost: None,
+        long_context_requests: 0,
+        sidechain: None,
     };
     for row in rows {
         totals.input_tokens += row.input_tokens;
@@ -1939,6 +5675,10 @@ fn calculate_session_totals(rows: &[SessionRow]) -> Totals {
         totals.cache_read_tokens += row.cache_read_tokens;
         totals.total_tokens += row.total_tokens;
         totals.cost_usd += row.cost_usd;
+        totals.costs += row.costs;
+        add_converted(&mut totals.converted_cost, row.converted_cost);
+        totals.long_context_requests += row.long_context_requests;
+        add_split(&mut totals.sidechain, row.sidechain);
     }
     totals
 }
@@ -1950,7 +5690,11 @@ fn calculate_block_totals(rows: &[BlockRow]) -> Totals {
         cache_creation_tokens: 0,
         cache_read_tokens: 0,
         total_tokens: 0,
-        cost_usd: 0.0,
+        cost_usd: Money::ZERO,
+        costs: CostBreakdown::default(),
+        converted_cost: None,
+        long_context_requests: 0,
+        sidechain: None,
     };
     for row in rows {
         totals.input_tokens += row.input_tokens;
@@ -1959,6 +5703,10 @@ fn calculate_block_totals(rows: &[BlockRow]) -> Totals {
         totals.cache_read_tokens += row.cache_read_tokens;
         totals.total_tokens += row.total_tokens;
         totals.cost_usd += row.cost_usd;
+        totals.costs += row.costs;
+        add_converted(&mut totals.converted_cost, row.converted_cost);
+        totals.long_context_requests += row.long_context_requests;
+        add_split(&mut totals.sidechain, row.sidechain);
     }
     totals
 }
@@ -1980,6 +5728,9 @@ fn output_rows(
     if breakdown && !report.model_breakdowns.is_empty() {
         print_model_breakdowns(&report.model_breakdowns, locale);
     }
+    print_long_context_note(report.totals.long_context_requests, locale);
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
 }
 
 fn output_sessions(report: SessionReport, json: bool, locale: &Locale) {
@@ -1992,6 +5743,9 @@ fn output_sessions(report: SessionReport, json: bool, locale: &Locale) {
     if !report.model_breakdowns.is_empty() {
         print_model_breakdowns(&report.model_breakdowns, locale);
     }
+    print_long_context_note(report.totals.long_context_requests, locale);
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
 }
 
 fn output_blocks(
@@ -2013,11 +5767,18 @@ fn output_blocks(
         report.token_limit,
         locale,
     );
+    if let Some(burn) = &report.burn_rate {
+        print_burn_rate(burn, locale);
+    }
     if breakdown && !report.model_breakdowns.is_empty() {
         print_model_breakdowns(&report.model_breakdowns, locale);
     }
+    print_long_context_note(report.totals.long_context_requests, locale);
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_blocks_live_cli(
     live_source: &mut LiveEventSource,
     tz: &Tz,
@@ -2027,12 +5788,13 @@ fn run_blocks_live_cli(
     recent_days: u32,
     session_length_hours: u32,
     opts: CommonOptions,
+    strict_pricing: bool,
     refresh_seconds: u64,
     rx: Receiver<()>,
 ) -> Result<()> {
     loop {
         live_source.refresh()?;
-        let report = build_blocks_report(
+        let mut report = build_blocks_report(
             live_source.events(),
             tz,
             locale,
@@ -2042,6 +5804,8 @@ fn run_blocks_live_cli(
             session_length_hours,
             opts.breakdown,
         )?;
+        report.duplicates_removed = live_source.duplicates_removed();
+        ensure_priced(&report.unpriced_models, strict_pricing)?;
         print!("\x1B[2J\x1B[H");
         output_blocks(report, opts.json, opts.compact, opts.breakdown, locale);
         match rx.recv_timeout(Duration::from_secs(refresh_seconds)) {
@@ -2052,6 +5816,7 @@ fn run_blocks_live_cli(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_blocks_live_tui(
     live_source: &mut LiveEventSource,
     tz: &Tz,
@@ -2061,6 +5826,7 @@ fn run_blocks_live_tui(
     recent_days: u32,
     session_length_hours: u32,
     breakdown: bool,
+    strict_pricing: bool,
     refresh_seconds: u64,
     rx: Receiver<()>,
 ) -> Result<()> {
@@ -2076,7 +5842,7 @@ fn run_blocks_live_tui(
     let res = (|| -> Result<()> {
         loop {
             live_source.refresh()?;
-            let report = build_blocks_report(
+            let mut report = build_blocks_report(
                 live_source.events(),
                 tz,
                 locale,
@@ -2086,104 +5852,562 @@ fn run_blocks_live_tui(
                 session_length_hours,
                 breakdown,
             )?;
-            terminal.draw(|f| render_blocks_tui(f, &report.rows, &report.totals, locale))?;
+            report.duplicates_removed = live_source.duplicates_removed();
+            ensure_priced(&report.unpriced_models, strict_pricing)?;
+            terminal.draw(|f| render_blocks_tui(f, &report, locale))?;
 
             while event::poll(Duration::from_millis(100))? {
-                if let Event::Key(k) = event::read()? {
-                    if k.kind == KeyEventKind::Press
-                        && matches!(k.code, KeyCode::Char('q') | KeyCode::Esc)
-                    {
-                        return Ok(());
-                    }
+                if let Event::Key(k) = event::read()?
+                    && k.kind == KeyEventKind::Press
+                    && matches!(k.code, KeyCode::Char('q') | KeyCode::Esc)
+                {
+                    return Ok(());
                 }
             }
 
-            match rx.recv_timeout(Duration::from_secs(refresh_seconds)) {
-                Ok(_) | Err(RecvTimeoutError::Timeout) => continue,
-                Err(RecvTimeoutError::Disconnected) => break,
-            }
+            match rx.recv_timeout(Duration::from_secs(refresh_seconds)) {
+                Ok(_) | Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        Ok(())
+    })();
+
+    disable_raw_mode().ok();
+    let _ = terminal.backend_mut().execute(LeaveAlternateScreen);
+    let _ = terminal.show_cursor();
+    res
+}
+
+fn render_blocks_tui(f: &mut ratatui::Frame<'_>, report: &BlocksReport, locale: &Locale) {
+    let rows = &report.rows;
+    let totals = &report.totals;
+    let burn_rate = report.burn_rate.as_ref();
+    let header_height = if burn_rate.is_some() { 4 } else { 3 };
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(header_height), Constraint::Min(0)])
+        .split(f.size());
+
+    let totals_text = format!(
+        "Input {} | Output {} | Cache {} | Total {} | Cost ${:.4} (q to quit)",
+        format_tokens(totals.input_tokens, locale),
+        format_tokens(totals.output_tokens, locale),
+        format_tokens(
+            totals.cache_creation_tokens + totals.cache_read_tokens,
+            locale
+        ),
+        format_tokens(totals.total_tokens, locale),
+        totals.cost_usd.usd()
+    );
+    let title = match report.duplicates_removed {
+        0 => "Totals".to_string(),
+        n => format!("Totals ({} duplicate entries removed)", n.to_formatted_string(locale)),
+    };
+    let totals_block = TuiBlock::default()
+        .borders(Borders::ALL)
+        .title(Span::raw(title));
+    let mut header_rows = vec![TuiRow::new(vec![Cell::from(totals_text)])];
+    if let Some(burn) = burn_rate {
+        header_rows.push(TuiRow::new(vec![Cell::from(format_burn_rate(burn, locale))]));
+    }
+    let totals_table = TuiTable::new(header_rows, [Constraint::Percentage(100)])
+    .block(totals_block);
+    f.render_widget(totals_table, chunks[0]);
+
+    let header = ["Block", "Total", "Cost", "Models"];
+    let body: Vec<TuiRow> = rows
+        .iter()
+        .take(12)
+        .map(|r| {
+            let block = if r.is_gap || r.is_active {
+                format!("{} {}", r.block_start, block_status(r))
+            } else {
+                r.block_start.clone()
+            };
+            TuiRow::new(vec![
+                Cell::from(block),
+                Cell::from(format_tokens(r.total_tokens, locale)),
+                Cell::from(format_cost(r.cost_usd)),
+                Cell::from(join_set(&r.models)),
+            ])
+        })
+        .collect();
+
+    let table = TuiTable::new(
+        body,
+        [
+            Constraint::Percentage(40),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Percentage(30),
+        ],
+    )
+    .header(
+        TuiRow::new(header.iter().map(|h| Cell::from(*h)))
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(
+        TuiBlock::default()
+            .borders(Borders::ALL)
+            .title(Span::raw("Blocks")),
+    );
+    f.render_widget(table, chunks[1]);
+}
+
+fn output_audit(report: AuditReport, json: bool, locale: &Locale) {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
+        println!();
+        return;
+    }
+    let tolerance = report.tolerance_percent;
+    print_audit_table("Audit by day", &report.days, Some(&report.totals), None, locale);
+    println!();
+    print_audit_table("Audit by model", &report.models, None, Some(tolerance), locale);
+
+    let missing = &report.missing_cost_usd;
+    if missing.events > 0 {
+        println!(
+            "{}",
+            format!(
+                "{} events without costUSD (calculated {})",
+                missing.events.to_formatted_string(locale),
+                format_cost(missing.calculated_cost_usd)
+            )
+            .dimmed()
+        );
+    }
+    if report.over_tolerance.is_empty() {
+        println!(
+            "{}",
+            format!("All models within {tolerance}% of calculated cost").green()
+        );
+    } else {
+        println!(
+            "{}",
+            format!(
+                "Over {tolerance}% tolerance: {}",
+                report.over_tolerance.join(", ")
+            )
+            .red()
+            .bold()
+        );
+    }
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
+}
+
+fn output_branches(report: BranchReport, json: bool, compact: bool, locale: &Locale) {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
+        println!();
+        return;
+    }
+    if report.rows.is_empty() {
+        println!("No matching branch usage.");
+        return;
+    }
+    let totals = &report.totals;
+    let use_compact = compact || get_terminal_width() < 120;
+    println!("{}", "Branch usage".bold());
+    let branch = |row: &BranchRow| row.branch.clone().unwrap_or_else(|| "-".into());
+    let mut table = if use_compact {
+        let mut table =
+            SimpleTable::new(vec!["Repository", "Branch", "Sessions", "Total", "Cost"])
+                .header_style(|s| s.cyan().bold());
+        for row in &report.rows {
+            table.add_row(vec![
+                row.repository.clone(),
+                branch(row),
+                row.sessions.to_formatted_string(locale),
+                format_tokens_compact(row.total_tokens),
+                format_cost(row.cost_usd),
+            ]);
+        }
+        table.set_footer(vec![
+            "Total".yellow().bold().to_string(),
+            String::new(),
+            String::new(),
+            format_tokens_compact(totals.total_tokens).yellow().to_string(),
+            format_cost(totals.cost_usd).yellow().to_string(),
+        ]);
+        table
+    } else {
+        let mut table = SimpleTable::new(vec![
+            "Repository", "Branch", "Sessions", "Input", "Output", "C/W", "C/R", "Total", "Cost",
+            "Models",
+        ])
+        .header_style(|s| s.cyan().bold());
+        for row in &report.rows {
+            table.add_row(vec![
+                row.repository.clone(),
+                branch(row),
+                row.sessions.to_formatted_string(locale),
+                format_tokens(row.input_tokens, locale),
+                format_tokens(row.output_tokens, locale),
+                format_tokens(row.cache_creation_tokens, locale),
+                format_tokens(row.cache_read_tokens, locale),
+                format_tokens(row.total_tokens, locale),
+                format_cost(row.cost_usd),
+                format_models(&row.models),
+            ]);
+        }
+        table.set_footer(vec![
+            "Total".yellow().bold().to_string(),
+            String::new(),
+            String::new(),
+            format_tokens(totals.input_tokens, locale).yellow().to_string(),
+            format_tokens(totals.output_tokens, locale).yellow().to_string(),
+            format_tokens(totals.cache_creation_tokens, locale).yellow().to_string(),
+            format_tokens(totals.cache_read_tokens, locale).yellow().to_string(),
+            format_tokens(totals.total_tokens, locale).yellow().to_string(),
+            format_cost(totals.cost_usd).yellow().to_string(),
+            String::new(),
+        ]);
+        table
+    };
+    add_converted_column(
+        &mut table,
+        report.rows.iter().map(|r| r.converted_cost),
+        totals.converted_cost,
+        locale,
+    );
+    table.print();
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
+}
+
+fn output_errors(report: ErrorsReport, json: bool, locale: &Locale) {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
+        println!();
+        return;
+    }
+    if report.events.is_empty() {
+        println!("No API errors or usage-limit hits in range.");
+        print_unpriced_warning(&report.unpriced_models, locale);
+        return;
+    }
+    let counts = |c: &ErrorCounts| {
+        [c.api_errors, c.overloaded, c.usage_limits, c.total]
+            .map(|n| n.to_formatted_string(locale))
+    };
+    println!("{}", "Errors by day".bold());
+    let mut table = SimpleTable::new(vec!["Date", "API errors", "Overloaded", "Limit hits", "Total"])
+        .header_style(|s| s.cyan().bold());
+    for day in &report.days {
+        let mut cells = vec![day.date.clone()];
+        cells.extend(counts(&day.counts));
+        table.add_row(cells);
+    }
+    let mut footer = vec!["Total".yellow().bold().to_string()];
+    footer.extend(counts(&report.totals).map(|c| c.yellow().to_string()));
+    table.set_footer(footer);
+    table.print();
+
+    println!("{}", "Errors by block".bold());
+    let mut table = SimpleTable::new(vec![
+        "Block Start", "API errors", "Overloaded", "Limit hits", "Limit hit at", "Resets at",
+        "Total", "Cost",
+    ])
+    .header_style(|s| s.cyan().bold());
+    for block in &report.blocks {
+        let mut cells = vec![block.block_start.clone()];
+        cells.extend(counts(&block.counts).into_iter().take(3));
+        cells.push(block.first_limit_hit.clone().unwrap_or_default());
+        cells.push(block.limit_resets_at.clone().unwrap_or_default());
+        cells.push(format_tokens(block.total_tokens, locale));
+        cells.push(format_cost(block.cost_usd));
+        table.add_row(cells);
+    }
+    table.print();
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
+}
+
+fn output_tools(report: ToolsReport, json: bool, locale: &Locale) {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
+        println!();
+        return;
+    }
+    if report.rows.is_empty() {
+        println!("No tool calls in range.");
+        print_unpriced_warning(&report.unpriced_models, locale);
+        return;
+    }
+    let totals = &report.totals;
+    println!("{}", "Tool usage".bold());
+    let mut table = SimpleTable::new(vec!["Tool", "Calls", "Turns", "Sessions", "Tokens", "Cost"])
+        .header_style(|s| s.cyan().bold());
+    for row in &report.rows {
+        table.add_row(vec![
+            row.tool.clone(),
+            row.usage.invocations.to_formatted_string(locale),
+            row.usage.turns.to_formatted_string(locale),
+            row.usage.sessions.to_formatted_string(locale),
+            format_tokens(row.usage.total_tokens, locale),
+            format_cost(row.usage.cost_usd),
+        ]);
+    }
+    table.set_footer(vec![
+        "Total".yellow().bold().to_string(),
+        totals.invocations.to_formatted_string(locale).yellow().to_string(),
+        totals.turns.to_formatted_string(locale).yellow().to_string(),
+        String::new(),
+        format_tokens(totals.total_tokens, locale).yellow().to_string(),
+        format_cost(totals.cost_usd).yellow().to_string(),
+    ]);
+    add_converted_column(
+        &mut table,
+        report.rows.iter().map(|r| r.usage.converted_cost),
+        totals.converted_cost,
+        locale,
+    );
+    table.print();
+
+    if !report.mcp_servers.is_empty() {
+        println!("{}", "MCP servers".bold());
+        let mut table =
+            SimpleTable::new(vec!["Server", "Tools", "Calls", "Turns", "Tokens", "Cost"])
+                .header_style(|s| s.cyan().bold());
+        for row in &report.mcp_servers {
+            table.add_row(vec![
+                row.server.clone(),
+                row.tools.len().to_formatted_string(locale),
+                row.usage.invocations.to_formatted_string(locale),
+                row.usage.turns.to_formatted_string(locale),
+                format_tokens(row.usage.total_tokens, locale),
+                format_cost(row.usage.cost_usd),
+            ]);
+        }
+        table.print();
+    }
+    let rest = &report.without_tools;
+    if rest.turns > 0 {
+        println!(
+            "Turns without tool calls: {} ({} tokens, {})",
+            rest.turns.to_formatted_string(locale),
+            format_tokens(rest.total_tokens, locale),
+            format_cost(rest.cost_usd)
+        );
+    }
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
+}
+
+fn output_versions(report: VersionsReport, json: bool, compact: bool, locale: &Locale) {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
+        println!();
+        return;
+    }
+    if report.rows.is_empty() {
+        println!("No matching usage.");
+        return;
+    }
+    let totals = &report.totals;
+    let use_compact = compact || get_terminal_width() < 120;
+    println!("{}", "Usage by Claude Code version".bold());
+    let version = |row: &VersionRow| row.version.clone().unwrap_or_else(|| "unknown".into());
+    let first_seen = |row: &VersionRow| row.first_seen.get(..10).unwrap_or_default().to_string();
+    let hit_ratio = |row: &VersionRow| {
+        let Some(ratio) = row.cache_hit_ratio else {
+            return "-".to_string();
+        };
+        match row.cache_hit_ratio_change {
+            Some(change) => format!("{:.1}% ({:+.1})", ratio * 100.0, change * 100.0),
+            None => format!("{:.1}%", ratio * 100.0),
+        }
+    };
+    let total_ratio = cache_hit_ratio(
+        totals.input_tokens,
+        totals.cache_creation_tokens,
+        totals.cache_read_tokens,
+    )
+    .map(|r| format!("{:.1}%", r * 100.0))
+    .unwrap_or_else(|| "-".into());
+    let mut table = if use_compact {
+        let mut table =
+            SimpleTable::new(vec!["Version", "First seen", "Total", "Cache hit", "Cost"])
+                .header_style(|s| s.cyan().bold());
+        for row in &report.rows {
+            table.add_row(vec![
+                version(row),
+                first_seen(row),
+                format_tokens_compact(row.total_tokens),
+                hit_ratio(row),
+                format_cost(row.cost_usd),
+            ]);
+        }
+        table.set_footer(vec![
+            "Total".yellow().bold().to_string(),
+            String::new(),
+            format_tokens_compact(totals.total_tokens).yellow().to_string(),
+            total_ratio.yellow().to_string(),
+            format_cost(totals.cost_usd).yellow().to_string(),
+        ]);
+        table
+    } else {
+        let mut table = SimpleTable::new(vec![
+            "Version", "First seen", "Last seen", "Sessions", "Input", "Output", "C/W", "C/R",
+            "Total", "Cache hit", "Cost",
+        ])
+        .header_style(|s| s.cyan().bold());
+        for row in &report.rows {
+            table.add_row(vec![
+                version(row),
+                first_seen(row),
+                row.last_seen.get(..10).unwrap_or_default().to_string(),
+                row.sessions.to_formatted_string(locale),
+                format_tokens(row.input_tokens, locale),
+                format_tokens(row.output_tokens, locale),
+                format_tokens(row.cache_creation_tokens, locale),
+                format_tokens(row.cache_read_tokens, locale),
+                format_tokens(row.total_tokens, locale),
+                hit_ratio(row),
+                format_cost(row.cost_usd),
+            ]);
+        }
+        table.set_footer(vec![
+            "Total".yellow().bold().to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            format_tokens(totals.input_tokens, locale).yellow().to_string(),
+            format_tokens(totals.output_tokens, locale).yellow().to_string(),
+            format_tokens(totals.cache_creation_tokens, locale).yellow().to_string(),
+            format_tokens(totals.cache_read_tokens, locale).yellow().to_string(),
+            format_tokens(totals.total_tokens, locale).yellow().to_string(),
+            total_ratio.yellow().to_string(),
+            format_cost(totals.cost_usd).yellow().to_string(),
+        ]);
+        table
+    };
+    add_converted_column(
+        &mut table,
+        report.rows.iter().map(|r| r.converted_cost),
+        totals.converted_cost,
+        locale,
+    );
+    table.print();
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
+}
+
+fn output_plan(report: PlanReport, json: bool, locale: &Locale) {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
+        println!();
+        return;
+    }
+    if report.cycles.is_empty() {
+        println!("No matching usage for plan cycles");
+        return;
+    }
+    println!(
+        "{}",
+        format!(
+            "{} plan, {}/month, renews on day {}",
+            report.plan.label(),
+            format_cost(report.monthly_fee_usd),
+            report.renewal_day
+        )
+        .bold()
+    );
+    let mut table = SimpleTable::new(vec![
+        "Cycle", "Tokens", "API cost", "Plan fee", "Value", "Net", "Break-even",
+    ])
+    .header_style(|s| s.cyan().bold());
+    let cells = |cycle: &PlanCycle| {
+        let net = format_cost_delta(cycle.net_usd);
+        vec![
+            format_tokens(cycle.total_tokens, locale),
+            format_cost(cycle.api_cost_usd),
+            format_cost(cycle.plan_fee_usd),
+            format!("{:.1}x", cycle.value_multiplier),
+            if cycle.net_usd < Money::ZERO {
+                net.red().to_string()
+            } else {
+                net.green().to_string()
+            },
+        ]
+    };
+    for cycle in &report.cycles {
+        let mut label = format!("{}..{}", cycle.cycle_start, cycle.cycle_end);
+        if cycle.is_current {
+            label = format!("{label} {}", "current".green().bold());
         }
-        Ok(())
-    })();
-
-    disable_raw_mode().ok();
-    let _ = terminal.backend_mut().execute(LeaveAlternateScreen);
-    let _ = terminal.show_cursor();
-    res
+        let mut row = vec![label];
+        row.extend(cells(cycle));
+        row.push(cycle.break_even_date.clone().unwrap_or_else(|| "-".into()));
+        table.add_row(row);
+    }
+    let mut footer = vec!["Total".yellow().bold().to_string()];
+    footer.extend(cells(&report.totals));
+    footer.push(String::new());
+    table.set_footer(footer);
+    table.print();
+    print_unpriced_warning(&report.unpriced_models, locale);
+    print_duplicates_note(report.duplicates_removed, locale);
 }
 
-fn render_blocks_tui(
-    f: &mut ratatui::Frame<'_>,
-    rows: &[BlockRow],
-    totals: &Totals,
+fn print_audit_table(
+    title: &str,
+    rows: &[AuditRow],
+    totals: Option<&AuditRow>,
+    tolerance: Option<f64>,
     locale: &Locale,
 ) {
-    let chunks = Layout::default()
-        .constraints([Constraint::Length(3), Constraint::Min(0)])
-        .split(f.size());
-
-    let totals_text = format!(
-        "Input {} | Output {} | Cache {} | Total {} | Cost ${:.4} (q to quit)",
-        format_tokens(totals.input_tokens, locale),
-        format_tokens(totals.output_tokens, locale),
-        format_tokens(
-            totals.cache_creation_tokens + totals.cache_read_tokens,
-            locale
-        ),
-        format_tokens(totals.total_tokens, locale),
-        totals.cost_usd
-    );
-    let totals_block = TuiBlock::default()
-        .borders(Borders::ALL)
-        .title(Span::raw("Totals"));
-    let totals_table = TuiTable::new(
-        vec![TuiRow::new(vec![Cell::from(totals_text)])],
-        [Constraint::Percentage(100)],
-    )
-    .block(totals_block);
-    f.render_widget(totals_table, chunks[0]);
-
-    let header = ["Block", "Total", "Cost", "Models"];
-    let body: Vec<TuiRow> = rows
-        .iter()
-        .take(12)
-        .map(|r| {
-            TuiRow::new(vec![
-                Cell::from(r.block_start.clone()),
-                Cell::from(format_tokens(r.total_tokens, locale)),
-                Cell::from(format_cost(r.cost_usd)),
-                Cell::from(join_set(&r.models)),
-            ])
-        })
-        .collect();
-
-    let table = TuiTable::new(
-        body,
-        [
-            Constraint::Percentage(40),
-            Constraint::Length(12),
-            Constraint::Length(12),
-            Constraint::Percentage(30),
-        ],
-    )
-    .header(
-        TuiRow::new(header.iter().map(|h| Cell::from(*h)))
-            .style(Style::default().add_modifier(Modifier::BOLD)),
-    )
-    .block(
-        TuiBlock::default()
-            .borders(Borders::ALL)
-            .title(Span::raw("Blocks")),
-    );
-    f.render_widget(table, chunks[1]);
+    println!("{}", title.bold());
+    let cells = |row: &AuditRow| {
+        vec![
+            row.key.clone(),
+            row.events.to_formatted_string(locale),
+            format_cost(row.logged_cost_usd),
+            format_cost(row.calculated_cost_usd),
+            format_cost_delta(row.delta_usd),
+            row.delta_percent
+                .map_or_else(|| "-".into(), |p| format!("{p:+.2}%")),
+            row.missing_cost_usd_events.to_formatted_string(locale),
+        ]
+    };
+    let mut table = SimpleTable::new(vec![
+        "Key", "Events", "Logged", "Calculated", "Delta", "Delta %", "No costUSD",
+    ])
+    .header_style(|s| s.cyan().bold());
+    for row in rows {
+        let mut line = cells(row);
+        if tolerance.is_some_and(|t| row.exceeds(t)) {
+            line = line.into_iter().map(|c| c.red().to_string()).collect();
+        }
+        table.add_row(line);
+    }
+    if let Some(totals) = totals {
+        table.set_footer(
+            cells(totals)
+                .into_iter()
+                .map(|c| c.yellow().to_string())
+                .collect(),
+        );
+    }
+    table.print();
 }
 
-fn output_statusline(report: StatuslineReport, json: bool) {
+fn output_statusline(report: StatuslineReport, json: bool, template: Option<&[TemplateSegment]>) {
     if json {
         serde_json::to_writer_pretty(std::io::stdout(), &report).expect("write json");
         println!();
         return;
     }
+    if let Some(template) = template {
+        println!("{}", render_statusline_template(template, &report));
+        return;
+    }
+    if let Some(session) = &report.session {
+        print_session_statusline(session);
+        return;
+    }
     if let Some(day) = report.last_date {
         println!(
             "{} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
@@ -2211,6 +6435,263 @@ fn output_statusline(report: StatuslineReport, json: bool) {
     }
 }
 
+fn print_session_statusline(session: &StatuslineSession) {
+    let sep = "|".dimmed();
+    let mut line = String::new();
+    if let Some(model) = &session.model {
+        line.push_str(&format!("{} {} ", model.cyan().bold(), sep));
+    }
+    line.push_str(&format!(
+        "{} {} {} {} {}",
+        format_cost(session.session_cost_usd).yellow().bold(),
+        "session".dimmed(),
+        sep,
+        format_cost(session.today_cost_usd).yellow(),
+        "today".dimmed(),
+    ));
+    if let Some(cost) = session.block_cost_usd {
+        line.push_str(&format!(" {} {} {}", sep, format_cost(cost).yellow(), "block".dimmed()));
+        if let Some(remaining) = session.block_remaining_minutes {
+            line.push_str(&format!(" ({} left)", format_minutes(remaining)));
+        }
+    }
+    if let Some(burn) = &session.burn_rate {
+        line.push_str(&format!(" {} {}/h", sep, format_cost(burn.cost_per_hour)));
+    }
+    println!("{line}");
+}
+
+const STATUSLINE_FIELDS: &[&str] = &[
+    "date",
+    "input",
+    "output",
+    "cache_write",
+    "cache_read",
+    "total",
+    "cost",
+    "model",
+    "project",
+    "session_id",
+    "session_cost",
+    "today_cost",
+    "block_cost",
+    "block_remaining",
+    "burn_rate",
+];
+
+#[derive(Debug)]
+enum TemplateSegment {
+    Literal(String),
+    Field {
+        name: String,
+        rules: Vec<ColorRule>,
+        default_color: Option<String>,
+    },
+}
+
+/// `>10:red` in `{today_cost|>10:red|green}`: color the field when its numeric value matches.
+#[derive(Debug)]
+struct ColorRule {
+    op: Ordering,
+    inclusive: bool,
+    threshold: f64,
+    color: String,
+}
+
+impl ColorRule {
+    fn matches(&self, value: f64) -> bool {
+        let ord = value.partial_cmp(&self.threshold).unwrap_or(Ordering::Equal);
+        ord == self.op || (self.inclusive && ord == Ordering::Equal)
+    }
+}
+
+/// Parses `{field}` placeholders with optional `|<op><n>:<color>` rules and a trailing
+/// default color. `{{` and `}}` produce literal braces.
+fn parse_statusline_template(raw: &str) -> Result<Vec<TemplateSegment>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => spec.push(ch),
+                        None => anyhow::bail!("Unclosed '{{' in statusline format '{raw}'"),
+                    }
+                }
+                if !literal.is_empty() {
+                    segments.push(TemplateSegment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(parse_template_field(&spec)?);
+            }
+            '}' => anyhow::bail!("Unmatched '}}' in statusline format '{raw}'"),
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(TemplateSegment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+fn parse_template_field(spec: &str) -> Result<TemplateSegment> {
+    let mut parts = spec.split('|').map(str::trim);
+    let name = parts.next().unwrap_or_default().to_string();
+    if !STATUSLINE_FIELDS.contains(&name.as_str()) {
+        anyhow::bail!(
+            "Unknown statusline field '{{{}}}'. Available: {}",
+            name,
+            STATUSLINE_FIELDS.join(", ")
+        );
+    }
+    let mut rules = Vec::new();
+    let mut default_color = None;
+    for part in parts {
+        let Some((condition, color)) = part.split_once(':') else {
+            validate_color(part)?;
+            default_color = Some(part.to_string());
+            continue;
+        };
+        let (op, inclusive, number) = if let Some(n) = condition.strip_prefix(">=") {
+            (Ordering::Greater, true, n)
+        } else if let Some(n) = condition.strip_prefix("<=") {
+            (Ordering::Less, true, n)
+        } else if let Some(n) = condition.strip_prefix('>') {
+            (Ordering::Greater, false, n)
+        } else if let Some(n) = condition.strip_prefix('<') {
+            (Ordering::Less, false, n)
+        } else {
+            anyhow::bail!("Invalid condition '{condition}' in '{{{spec}}}', expected >, >=, < or <=");
+        };
+        let threshold = number
+            .trim()
+            .parse::<f64>()
+            .with_context(|| format!("Invalid threshold '{number}' in '{{{spec}}}'"))?;
+        validate_color(color)?;
+        rules.push(ColorRule {
+            op,
+            inclusive,
+            threshold,
+            color: color.to_string(),
+        });
+    }
+    Ok(TemplateSegment::Field {
+        name,
+        rules,
+        default_color,
+    })
+}
+
+fn validate_color(color: &str) -> Result<()> {
+    if apply_color("", color).is_none() {
+        anyhow::bail!(
+            "Unknown color '{color}'. Use red, green, yellow, blue, magenta, cyan, white, bold or dimmed"
+        );
+    }
+    Ok(())
+}
+
+fn apply_color(text: &str, color: &str) -> Option<String> {
+    let styled = match color {
+        "red" => text.red(),
+        "green" => text.green(),
+        "yellow" => text.yellow(),
+        "blue" => text.blue(),
+        "magenta" => text.magenta(),
+        "cyan" => text.cyan(),
+        "white" => text.white(),
+        "bold" => text.bold(),
+        "dimmed" => text.dimmed(),
+        _ => return None,
+    };
+    Some(styled.to_string())
+}
+
+/// Display text and, for numeric fields, the value thresholds are compared against.
+fn statusline_field(report: &StatuslineReport, name: &str) -> (String, Option<f64>) {
+    let totals = &report.totals;
+    let session = report.session.as_ref();
+    let tokens = |v: u64| (format_tokens_compact(v), Some(v as f64));
+    let cost = |v: Money| (format_cost(v), Some(v.usd()));
+    let missing = || ("-".to_string(), None);
+    match name {
+        "date" => (report.last_date.clone().unwrap_or_else(|| "-".into()), None),
+        "input" => tokens(totals.input_tokens),
+        "output" => tokens(totals.output_tokens),
+        "cache_write" => tokens(totals.cache_creation_tokens),
+        "cache_read" => tokens(totals.cache_read_tokens),
+        "total" => tokens(totals.total_tokens),
+        "cost" => cost(totals.cost_usd),
+        "model" => session
+            .and_then(|s| s.model.clone())
+            .map(|m| (m, None))
+            .unwrap_or_else(missing),
+        "project" => session
+            .and_then(|s| s.cwd.as_deref())
+            .and_then(|cwd| Path::new(cwd).file_name())
+            .map(|n| (n.to_string_lossy().to_string(), None))
+            .unwrap_or_else(missing),
+        "session_id" => session
+            .and_then(|s| s.session_id.clone())
+            .map(|id| (id, None))
+            .unwrap_or_else(missing),
+        "session_cost" => session.map(|s| cost(s.session_cost_usd)).unwrap_or_else(missing),
+        "today_cost" => session
+            .map(|s| s.today_cost_usd)
+            .map(cost)
+            .unwrap_or_else(|| cost(totals.cost_usd)),
+        "block_cost" => session
+            .and_then(|s| s.block_cost_usd)
+            .map(cost)
+            .unwrap_or_else(missing),
+        "block_remaining" => session
+            .and_then(|s| s.block_remaining_minutes)
+            .map(|m| (format_minutes(m), Some(m as f64)))
+            .unwrap_or_else(missing),
+        "burn_rate" => session
+            .and_then(|s| s.burn_rate.as_ref())
+            .map(|b| (format!("{}/h", format_cost(b.cost_per_hour)), Some(b.cost_per_hour.usd())))
+            .unwrap_or_else(missing),
+        _ => missing(),
+    }
+}
+
+fn render_statusline_template(template: &[TemplateSegment], report: &StatuslineReport) -> String {
+    let mut out = String::new();
+    for segment in template {
+        match segment {
+            TemplateSegment::Literal(text) => out.push_str(text),
+            TemplateSegment::Field {
+                name,
+                rules,
+                default_color,
+            } => {
+                let (text, value) = statusline_field(report, name);
+                let color = value
+                    .and_then(|v| rules.iter().find(|rule| rule.matches(v)))
+                    .map(|rule| &rule.color)
+                    .or(default_color.as_ref());
+                match color.and_then(|c| apply_color(&text, c)) {
+                    Some(colored) => out.push_str(&colored),
+                    None => out.push_str(&text),
+                }
+            }
+        }
+    }
+    out
+}
+
 fn print_rows_table(rows: &[Row], totals: &Totals, compact: bool, locale: &Locale, title: &str) {
     if rows.is_empty() {
         println!("No matching usage for {}", title.to_lowercase());
@@ -2246,9 +6727,21 @@ fn print_rows_table(rows: &[Row], totals: &Totals, compact: bool, locale: &Local
             String::new(),
             String::new(),
         ]);
+        add_converted_column(
+            &mut table,
+            rows.iter().map(|r| r.converted_cost),
+            totals.converted_cost,
+            locale,
+        );
+        add_sidechain_columns(
+            &mut table,
+            rows.iter().map(|r| r.sidechain),
+            totals.sidechain,
+            format_cost_compact,
+        );
         table.print();
     } else {
-        let mut table = SimpleTable::new(vec!["Period", "Input", "Output", "C/W", "C/R", "Total", "Cost", "Projects", "Models"])
+        let mut table = SimpleTable::new(vec!["Period", "Input", "Output", "C/W", "C/R", "Total", "In $", "Out $", "C/W $", "C/R $", "Cost", "Projects", "Models"])
             .header_style(|s| s.cyan().bold());
         for row in rows {
             table.add_row(vec![
@@ -2258,6 +6751,10 @@ fn print_rows_table(rows: &[Row], totals: &Totals, compact: bool, locale: &Local
                 format_tokens(row.cache_creation_tokens, locale),
                 format_tokens(row.cache_read_tokens, locale),
                 format_tokens(row.total_tokens, locale),
+                format_cost(row.costs.input_cost),
+                format_cost(row.costs.output_cost),
+                format_cost(row.costs.cache_write_cost),
+                format_cost(row.costs.cache_read_cost),
                 format_cost(row.cost_usd),
                 format_projects(&row.projects, max_projects),
                 format_models(&row.models),
@@ -2270,10 +6767,26 @@ fn print_rows_table(rows: &[Row], totals: &Totals, compact: bool, locale: &Local
             format_tokens(totals.cache_creation_tokens, locale).yellow().to_string(),
             format_tokens(totals.cache_read_tokens, locale).yellow().to_string(),
             format_tokens(totals.total_tokens, locale).yellow().to_string(),
+            format_cost(totals.costs.input_cost).yellow().to_string(),
+            format_cost(totals.costs.output_cost).yellow().to_string(),
+            format_cost(totals.costs.cache_write_cost).yellow().to_string(),
+            format_cost(totals.costs.cache_read_cost).yellow().to_string(),
             format_cost(totals.cost_usd).yellow().to_string(),
             String::new(),
             String::new(),
         ]);
+        add_converted_column(
+            &mut table,
+            rows.iter().map(|r| r.converted_cost),
+            totals.converted_cost,
+            locale,
+        );
+        add_sidechain_columns(
+            &mut table,
+            rows.iter().map(|r| r.sidechain),
+            totals.sidechain,
+            format_cost,
+        );
         table.print();
     }
 }
@@ -2295,7 +6808,7 @@ fn print_sessions_table(rows: &[SessionRow], totals: &Totals, locale: &Locale) {
         for row in rows {
             table.add_row(vec![
                 truncate_str(&row.session_id, 12),
-                shorten_project_name(&row.project),
+                row.project.clone(),
                 format_tokens_compact(row.input_tokens),
                 format_tokens_compact(row.output_tokens),
                 format_tokens_compact(row.total_tokens),
@@ -2312,20 +6825,36 @@ fn print_sessions_table(rows: &[SessionRow], totals: &Totals, locale: &Locale) {
             format_cost_compact(totals.cost_usd).yellow().to_string(),
             String::new(),
         ]);
+        add_converted_column(
+            &mut table,
+            rows.iter().map(|r| r.converted_cost),
+            totals.converted_cost,
+            locale,
+        );
+        add_sidechain_columns(
+            &mut table,
+            rows.iter().map(|r| r.sidechain),
+            totals.sidechain,
+            format_cost_compact,
+        );
         table.print();
     } else {
-        let mut table = SimpleTable::new(vec!["Session", "Project", "Last Activity", "Input", "Output", "C/W", "C/R", "Total", "Cost", "Models"])
+        let mut table = SimpleTable::new(vec!["Session", "Project", "Last Activity", "Input", "Output", "C/W", "C/R", "Total", "In $", "Out $", "C/W $", "C/R $", "Cost", "Models"])
             .header_style(|s| s.cyan().bold());
         for row in rows {
             table.add_row(vec![
                 truncate_str(&row.session_id, 16),
-                shorten_project_name(&row.project),
+                row.project.clone(),
                 row.last_activity.clone(),
                 format_tokens(row.input_tokens, locale),
                 format_tokens(row.output_tokens, locale),
                 format_tokens(row.cache_creation_tokens, locale),
                 format_tokens(row.cache_read_tokens, locale),
                 format_tokens(row.total_tokens, locale),
+                format_cost(row.costs.input_cost),
+                format_cost(row.costs.output_cost),
+                format_cost(row.costs.cache_write_cost),
+                format_cost(row.costs.cache_read_cost),
                 format_cost(row.cost_usd),
                 format_models(&row.models),
             ]);
@@ -2339,9 +6868,25 @@ fn print_sessions_table(rows: &[SessionRow], totals: &Totals, locale: &Locale) {
             format_tokens(totals.cache_creation_tokens, locale).yellow().to_string(),
             format_tokens(totals.cache_read_tokens, locale).yellow().to_string(),
             format_tokens(totals.total_tokens, locale).yellow().to_string(),
+            format_cost(totals.costs.input_cost).yellow().to_string(),
+            format_cost(totals.costs.output_cost).yellow().to_string(),
+            format_cost(totals.costs.cache_write_cost).yellow().to_string(),
+            format_cost(totals.costs.cache_read_cost).yellow().to_string(),
             format_cost(totals.cost_usd).yellow().to_string(),
             String::new(),
         ]);
+        add_converted_column(
+            &mut table,
+            rows.iter().map(|r| r.converted_cost),
+            totals.converted_cost,
+            locale,
+        );
+        add_sidechain_columns(
+            &mut table,
+            rows.iter().map(|r| r.sidechain),
+            totals.sidechain,
+            format_cost,
+        );
         table.print();
     }
 }
@@ -2367,8 +6912,27 @@ fn print_blocks_table(
         let mut table = SimpleTable::new(vec!["Block", "Total", "%Lim", "Cost", "Models"])
             .header_style(|s| s.cyan().bold());
         for row in rows {
+            if row.is_gap {
+                table.add_row(vec![
+                    format!("{} {}", row.block_start, block_status(row)).dimmed().to_string(),
+                    "-".dimmed().to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ]);
+                continue;
+            }
+            let mut block = if let Some(remaining) = row.remaining_minutes {
+                let status = format!("active, {} left", format_minutes(remaining));
+                format!("{} {}", row.block_start, status.green().bold())
+            } else {
+                row.block_start.clone()
+            };
+            if let Some(note) = error_note(row) {
+                block = format!("{block} {}", note.red().bold());
+            }
             table.add_row(vec![
-                row.block_start.clone(),
+                block,
                 format_tokens_compact(row.total_tokens),
                 row.percent_of_limit.map(|p| format!("{:.0}%", p)).unwrap_or_else(|| "-".into()),
                 format_cost_compact(row.cost_usd),
@@ -2382,20 +6946,51 @@ fn print_blocks_table(
             format_cost_compact(totals.cost_usd).yellow().to_string(),
             String::new(),
         ]);
+        add_converted_column(
+            &mut table,
+            rows.iter().map(|r| r.converted_cost),
+            totals.converted_cost,
+            locale,
+        );
+        add_sidechain_columns(
+            &mut table,
+            rows.iter().map(|r| r.sidechain),
+            totals.sidechain,
+            format_cost_compact,
+        );
         table.print();
     } else {
-        let mut table = SimpleTable::new(vec!["Block Start", "Block End", "Input", "Output", "C/W", "C/R", "Total", "%Lim", "Cost", "Models"])
+        let mut table = SimpleTable::new(vec!["Block Start", "Block End", "Status", "Input", "Output", "C/W", "C/R", "Total", "%Lim", "In $", "Out $", "C/W $", "C/R $", "Cost", "Models"])
             .header_style(|s| s.cyan().bold());
         for row in rows {
+            if row.is_gap {
+                let mut cells = vec![
+                    row.block_start.dimmed().to_string(),
+                    row.block_end.dimmed().to_string(),
+                    block_status(row).dimmed().to_string(),
+                ];
+                cells.resize(15, String::new());
+                table.add_row(cells);
+                continue;
+            }
             table.add_row(vec![
                 row.block_start.clone(),
                 row.block_end.clone(),
+                if row.errors.is_some() {
+                    block_status(row).red().bold().to_string()
+                } else {
+                    block_status(row).green().bold().to_string()
+                },
                 format_tokens(row.input_tokens, locale),
                 format_tokens(row.output_tokens, locale),
                 format_tokens(row.cache_creation_tokens, locale),
                 format_tokens(row.cache_read_tokens, locale),
                 format_tokens(row.total_tokens, locale),
                 row.percent_of_limit.map(|p| format!("{:.1}%", p)).unwrap_or_else(|| "-".into()),
+                format_cost(row.costs.input_cost),
+                format_cost(row.costs.output_cost),
+                format_cost(row.costs.cache_write_cost),
+                format_cost(row.costs.cache_read_cost),
                 format_cost(row.cost_usd),
                 format_models(&row.models),
             ]);
@@ -2406,19 +7001,59 @@ fn print_blocks_table(
         table.set_footer(vec![
             "TOTAL".yellow().bold().to_string(),
             String::new(),
+            String::new(),
             format_tokens(totals.input_tokens, locale).yellow().to_string(),
             format_tokens(totals.output_tokens, locale).yellow().to_string(),
             format_tokens(totals.cache_creation_tokens, locale).yellow().to_string(),
             format_tokens(totals.cache_read_tokens, locale).yellow().to_string(),
             format_tokens(totals.total_tokens, locale).yellow().to_string(),
             total_pct.yellow().to_string(),
+            format_cost(totals.costs.input_cost).yellow().to_string(),
+            format_cost(totals.costs.output_cost).yellow().to_string(),
+            format_cost(totals.costs.cache_write_cost).yellow().to_string(),
+            format_cost(totals.costs.cache_read_cost).yellow().to_string(),
             format_cost(totals.cost_usd).yellow().to_string(),
             String::new(),
         ]);
+        add_converted_column(
+            &mut table,
+            rows.iter().map(|r| r.converted_cost),
+            totals.converted_cost,
+            locale,
+        );
+        add_sidechain_columns(
+            &mut table,
+            rows.iter().map(|r| r.sidechain),
+            totals.sidechain,
+            format_cost,
+        );
         table.print();
     }
 }
 
+fn print_burn_rate(burn: &BurnRate, locale: &Locale) {
+    println!("{} {}", "Active block:".bold(), format_burn_rate(burn, locale));
+}
+
+fn format_burn_rate(burn: &BurnRate, locale: &Locale) -> String {
+    let mut text = format!(
+        "burn {} tok/min, {}/h (last {}m) | projected {} tokens, {} at block end",
+        format_tokens(burn.tokens_per_minute.round() as u64, locale),
+        format_cost(burn.cost_per_hour),
+        burn.window_minutes,
+        format_tokens(burn.projected_total_tokens, locale),
+        format_cost(burn.projected_cost_usd),
+    );
+    if let Some(at) = burn
+        .limit_reached_at
+        .as_deref()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+    {
+        text.push_str(&format!(" | limit reached ~{}", at.format("%H:%M")));
+    }
+    text
+}
+
 fn print_model_breakdowns(models: &[ModelBreakdown], locale: &Locale) {
     if models.is_empty() {
         return;
@@ -2432,22 +7067,69 @@ fn print_model_breakdowns(models: &[ModelBreakdown], locale: &Locale) {
     });
 
     println!("\n{}", "Model breakdowns".bold());
-    let mut table = SimpleTable::new(vec!["Model", "Input", "Output", "C/W", "C/R", "Total", "Cost"])
+    let mut table = SimpleTable::new(vec!["Model", "Input", "Output", "C/W 5m", "C/W 1h", "C/R", "Total", "In $", "Out $", "C/W $", "C/R $", "Cost"])
         .header_style(|s| s.cyan().bold());
     for row in sorted {
         table.add_row(vec![
             format_model_name(&row.model).dimmed().to_string(),
             format_tokens(row.input_tokens, locale).dimmed().to_string(),
             format_tokens(row.output_tokens, locale).dimmed().to_string(),
-            format_tokens(row.cache_creation_tokens, locale).dimmed().to_string(),
+            format_tokens(row.cache_creation_5m_tokens, locale).dimmed().to_string(),
+            format_tokens(row.cache_creation_1h_tokens, locale).dimmed().to_string(),
             format_tokens(row.cache_read_tokens, locale).dimmed().to_string(),
             format_tokens(row.total_tokens, locale).dimmed().to_string(),
+            format_cost(row.costs.input_cost).dimmed().to_string(),
+            format_cost(row.costs.output_cost).dimmed().to_string(),
+            format_cost(row.costs.cache_write_cost).dimmed().to_string(),
+            format_cost(row.costs.cache_read_cost).dimmed().to_string(),
             format_cost(row.cost_usd).dimmed().to_string(),
         ]);
     }
     table.print();
 }
 
+fn print_long_context_note(count: u64, locale: &Locale) {
+    if count > 0 {
+        println!(
+            "{}",
+            format!(
+                "{} requests billed at long-context rates (prompt over {} tokens)",
+                count.to_formatted_string(locale),
+                LONG_CONTEXT_THRESHOLD_TOKENS.to_formatted_string(locale)
+            )
+            .dimmed()
+        );
+    }
+}
+
+fn print_unpriced_warning(unpriced: &[UnpricedModel], locale: &Locale) {
+    for model in unpriced {
+        println!(
+            "{}",
+            format!(
+                "Warning: no pricing for {} ({} requests, {} tokens counted as $0.00)",
+                model.model,
+                model.requests.to_formatted_string(locale),
+                model.total_tokens.to_formatted_string(locale)
+            )
+            .yellow()
+        );
+    }
+}
+
+fn print_duplicates_note(count: usize, locale: &Locale) {
+    if count > 0 {
+        println!(
+            "{}",
+            format!(
+                "{} duplicate entries removed",
+                count.to_formatted_string(locale)
+            )
+            .dimmed()
+        );
+    }
+}
+
 fn format_tokens(value: u64, locale: &Locale) -> String {
     value.to_formatted_string(locale)
 }
@@ -2464,13 +7146,32 @@ fn format_tokens_compact(value: u64) -> String {
     }
 }
 
-fn format_cost(value: f64) -> String {
-    let int_part = value.trunc() as u64;
-    let frac_part = ((value.fract() * 100.0).round() as u64) % 100;
-    format!("${}.{:02}", int_part.to_formatted_string(&Locale::en), frac_part)
+/// Per-million rate with cents, keeping extra digits only when the rate has them.
+fn format_rate(value: f64) -> String {
+    let text = format!("{value:.4}");
+    let trimmed = text.trim_end_matches('0');
+    let decimals = trimmed.split('.').nth(1).map_or(0, str::len);
+    if decimals < 2 {
+        format!("${value:.2}")
+    } else {
+        format!("${trimmed}")
+    }
+}
+
+fn format_cost_delta(value: Money) -> String {
+    let sign = if value < Money::ZERO { "-" } else { "+" };
+    format!("{sign}{}", format_cost(value.abs()))
+}
+
+fn format_cost(value: Money) -> String {
+    let cents = (value.0 as f64 / 10_000.0).round() as i64;
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{sign}${}.{:02}", (cents / 100).to_formatted_string(&Locale::en), cents % 100)
 }
 
-fn format_cost_compact(value: f64) -> String {
+fn format_cost_compact(value: Money) -> String {
+    let value = value.usd();
     if value >= 1_000_000.0 {
         format!("${:.1}M", value / 1_000_000.0)
     } else if value >= 1_000.0 {
@@ -2480,6 +7181,80 @@ fn format_cost_compact(value: f64) -> String {
     }
 }
 
+/// Amount in micro-units of `currency`, with its symbol placed the way the locale
+/// writes it: after the number for fr/de/es/it ("1.234,56 €"), before it otherwise.
+fn format_converted(value: Money, currency: &Currency, locale: &Locale) -> String {
+    let digits = currency.minor_digits();
+    let minor = (value.0 as f64 / 10f64.powi(6 - digits as i32)).round() as i64;
+    let scale = 10u64.pow(digits);
+    let abs = minor.unsigned_abs();
+    let mut number = (abs / scale).to_formatted_string(locale);
+    if digits > 0 {
+        number.push_str(locale.decimal());
+        number.push_str(&format!("{:0width$}", abs % scale, width = digits as usize));
+    }
+    let sign = if minor < 0 { "-" } else { "" };
+    let symbol_after = matches!(locale, Locale::fr | Locale::de | Locale::es | Locale::it);
+    match (currency.symbol(), symbol_after) {
+        (Some(symbol), true) => format!("{sign}{number} {symbol}"),
+        (Some(symbol), false) => format!("{sign}{symbol}{number}"),
+        (None, true) => format!("{sign}{number} {}", currency.code),
+        (None, false) => format!("{sign}{} {number}", currency.code),
+    }
+}
+
+/// Adds a "Cost (EUR)"-style column after `Cost` when `--currency` is set.
+fn add_converted_column(
+    table: &mut SimpleTable,
+    amounts: impl Iterator<Item = Option<Money>>,
+    total: Option<Money>,
+    locale: &Locale,
+) {
+    let Some(currency) = CURRENCY.get() else {
+        return;
+    };
+    let format = |amount: Option<Money>| {
+        amount.map_or_else(String::new, |m| format_converted(m, currency, locale))
+    };
+    let cells = amounts.map(format).collect();
+    table.insert_column_after(
+        "Cost",
+        format!("Cost ({})", currency.code),
+        cells,
+        format(total).yellow().to_string(),
+    );
+}
+
+/// Adds "Main $" and "Subagent $" columns after "Cost" with `--sidechain split`.
+fn add_sidechain_columns(
+    table: &mut SimpleTable,
+    splits: impl Iterator<Item = Option<SidechainSplit>>,
+    total: Option<SidechainSplit>,
+    format_amount: fn(Money) -> String,
+) {
+    if sidechain_mode() != SidechainMode::Split {
+        return;
+    }
+    let splits: Vec<_> = splits.collect();
+    let format = |split: Option<SidechainSplit>, side: fn(&SidechainSplit) -> Money| {
+        split.map_or_else(String::new, |s| format_amount(side(&s)))
+    };
+    let subagent = |s: &SidechainSplit| s.subagent.cost_usd;
+    let main = |s: &SidechainSplit| s.main.cost_usd;
+    table.insert_column_after(
+        "Cost",
+        "Subagent $".to_string(),
+        splits.iter().map(|s| format(*s, subagent)).collect(),
+        format(total, subagent).yellow().to_string(),
+    );
+    table.insert_column_after(
+        "Cost",
+        "Main $".to_string(),
+        splits.iter().map(|s| format(*s, main)).collect(),
+        format(total, main).yellow().to_string(),
+    );
+}
+
 fn join_set(set: &BTreeSet<String>) -> String {
     if set.is_empty() {
         return "-".into();
@@ -2518,23 +7293,6 @@ fn get_terminal_width() -> u16 {
     terminal_size().map(|(w, _)| w).unwrap_or(120)
 }
 
-/// Shorten a project path by removing common prefixes
-fn shorten_project_name(name: &str) -> String {
-    // Remove common path prefixes like "-Users-joel-Work-"
-    let name = name.strip_prefix("-Users-").unwrap_or(name);
-    // Find the last component after removing user path
-    if let Some(idx) = name.find('-') {
-        // Skip username, get the rest
-        let rest = &name[idx + 1..];
-        if let Some(idx2) = rest.find('-') {
-            // Skip "Work" or similar, get actual project
-            return rest[idx2 + 1..].to_string();
-        }
-        return rest.to_string();
-    }
-    name.to_string()
-}
-
 /// Truncate a string to max length, adding "..." if needed
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
@@ -2546,17 +7304,16 @@ fn truncate_str(s: &str, max_len: usize) -> String {
     }
 }
 
-/// Format projects for display, limiting count and shortening names
+/// Format projects for display, limiting count
 fn format_projects(set: &BTreeSet<String>, max_display: usize) -> String {
     if set.is_empty() {
         return "-".into();
     }
-    let shortened: Vec<String> = set.iter().map(|p| shorten_project_name(p)).collect();
-    if shortened.len() <= max_display {
-        shortened.join(", ")
+    let names: Vec<&str> = set.iter().map(String::as_str).collect();
+    if names.len() <= max_display {
+        names.join(", ")
     } else {
-        let displayed: Vec<_> = shortened.iter().take(max_display).cloned().collect();
-        format!("{}, +{} more", displayed.join(", "), shortened.len() - max_display)
+        format!("{}, +{} more", names[..max_display].join(", "), names.len() - max_display)
     }
 }
 
@@ -2591,6 +7348,28 @@ impl SimpleTable {
         self.footer = Some(footer);
     }
 
+    /// Inserts a column after the header named `after`; `cells` line up with the rows.
+    fn insert_column_after(
+        &mut self,
+        after: &str,
+        header: String,
+        cells: Vec<String>,
+        footer: String,
+    ) {
+        let index = self
+            .headers
+            .iter()
+            .position(|h| h == after)
+            .map_or(self.headers.len(), |i| i + 1);
+        self.headers.insert(index, header);
+        for (row, cell) in self.rows.iter_mut().zip(cells) {
+            row.insert(index.min(row.len()), cell);
+        }
+        if let Some(row) = &mut self.footer {
+            row.insert(index.min(row.len()), footer);
+        }
+    }
+
     fn print(&self) {
         let num_cols = self.headers.len();
         let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
@@ -2706,10 +7485,10 @@ fn extract_project_name(path: &Path) -> String {
         .components()
         .map(|c| c.as_os_str().to_string_lossy().to_string())
         .collect::<Vec<_>>();
-    if let Some(idx) = components.iter().position(|c| c == PROJECTS_DIR) {
-        if let Some(name) = components.get(idx + 1) {
-            return name.clone();
-        }
+    if let Some(idx) = components.iter().position(|c| c == PROJECTS_DIR)
+        && let Some(name) = components.get(idx + 1)
+    {
+        return name.clone();
     }
     "unknown".into()
 }
@@ -2734,7 +7513,7 @@ fn aggregate_models_from_rows(rows: &[Row]) -> Vec<ModelBreakdown> {
     for row in rows {
         for mb in &row.model_breakdowns {
             map.entry(mb.model.clone())
-                .or_insert_with(ModelAccumulator::default)
+                .or_default()
                 .add_from_breakdown(mb);
         }
     }
@@ -2748,7 +7527,7 @@ fn aggregate_models_from_session_rows(rows: &[SessionRow]) -> Vec<ModelBreakdown
     for row in rows {
         for mb in &row.model_breakdowns {
             map.entry(mb.model.clone())
-                .or_insert_with(ModelAccumulator::default)
+                .or_default()
                 .add_from_breakdown(mb);
         }
     }
@@ -2762,7 +7541,7 @@ fn aggregate_models_from_blocks(rows: &[BlockRow]) -> Vec<ModelBreakdown> {
     for row in rows {
         for mb in &row.model_breakdowns {
             map.entry(mb.model.clone())
-                .or_insert_with(ModelAccumulator::default)
+                .or_default()
                 .add_from_breakdown(mb);
         }
     }
@@ -2776,9 +7555,36 @@ impl ModelAccumulator {
         self.input_tokens += mb.input_tokens;
         self.output_tokens += mb.output_tokens;
         self.cache_creation_tokens += mb.cache_creation_tokens;
+        self.cache_creation_5m_tokens += mb.cache_creation_5m_tokens;
+        self.cache_creation_1h_tokens += mb.cache_creation_1h_tokens;
         self.cache_read_tokens += mb.cache_read_tokens;
         self.cost_usd += mb.cost_usd;
+        self.costs += mb.costs;
+        add_converted(&mut self.converted_cost, mb.converted_cost);
+        self.unpriced_requests += mb.unpriced_requests;
+        self.unpriced_tokens += mb.unpriced_tokens;
+    }
+}
+
+fn collect_unpriced_models<'a>(
+    breakdowns: impl IntoIterator<Item = &'a ModelBreakdown>,
+) -> Vec<UnpricedModel> {
+    let mut map: BTreeMap<&str, UnpricedModel> = BTreeMap::new();
+    for mb in breakdowns {
+        if mb.unpriced_requests == 0 {
+            continue;
+        }
+        let entry = map.entry(&mb.model).or_insert_with(|| UnpricedModel {
+            model: mb.model.clone(),
+            requests: 0,
+            total_tokens: 0,
+        });
+        entry.requests += mb.unpriced_requests;
+        entry.total_tokens += mb.unpriced_tokens;
     }
+    let mut models: Vec<_> = map.into_values().collect();
+    models.sort_by_key(|m| std::cmp::Reverse(m.total_tokens));
+    models
 }
 
 fn locale_to_string(locale: &Locale) -> String {
@@ -2793,6 +7599,56 @@ fn locale_to_string(locale: &Locale) -> String {
     }
 }
 
+/// Status cell for a block: idle length for gaps, elapsed/remaining for the active block.
+fn block_status(row: &BlockRow) -> String {
+    if row.is_gap {
+        let idle = DateTime::parse_from_rfc3339(&row.block_end)
+            .ok()
+            .zip(DateTime::parse_from_rfc3339(&row.block_start).ok())
+            .map(|(end, start)| (end - start).num_minutes())
+            .unwrap_or(0);
+        return format!("idle {}", format_minutes(idle));
+    }
+    let active = match (row.elapsed_minutes, row.remaining_minutes) {
+        (Some(elapsed), Some(remaining)) => format!(
+            "ACTIVE {} elapsed, {} left",
+            format_minutes(elapsed),
+            format_minutes(remaining)
+        ),
+        _ => String::new(),
+    };
+    match error_note(row) {
+        Some(note) if active.is_empty() => note,
+        Some(note) => format!("{active}, {note}"),
+        None => active,
+    }
+}
+
+/// "LIMIT HIT (resets 15:00)" for blocks that ran into the usage limit, else the error count.
+fn error_note(row: &BlockRow) -> Option<String> {
+    let errors = row.errors?;
+    if errors.usage_limits == 0 {
+        let plural = if errors.total == 1 { "" } else { "s" };
+        return Some(format!("{} API error{plural}", errors.total));
+    }
+    let resets = row
+        .limit_resets_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| format!(" (resets {})", at.format("%H:%M")))
+        .unwrap_or_default();
+    Some(format!("LIMIT HIT{resets}"))
+}
+
+fn format_minutes(minutes: i64) -> String {
+    let minutes = minutes.max(0);
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
 fn format_block_range(start: DateTime<Tz>, hours: u32) -> String {
     let end = start + ChronoDuration::hours(hours as i64);
     format!(
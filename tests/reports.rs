@@ -1,10 +1,31 @@
 use assert_cmd::prelude::*;
 use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
+
+/// Cache directory for the running test, so runs never touch ~/.cache or each other's
+/// index. Emptied the first time each test asks for it.
+fn cache_home() -> PathBuf {
+    static CLEARED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+    let name = std::thread::current()
+        .name()
+        .unwrap_or("main")
+        .replace("::", "-");
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("cache")
+        .join(&name);
+    if CLEARED.lock().unwrap().insert(name) {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    dir
+}
 
 fn run_json(args: &[&str]) -> Value {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
     let output = cmd
+        .env("XDG_CACHE_HOME", cache_home())
         .args(args)
         .assert()
         .success()
@@ -80,10 +101,1545 @@ fn blocks_report_matches_fixture() {
         "--breakdown",
     ]);
     let rows = v["rows"].as_array().unwrap();
-    assert_eq!(rows.len(), 4);
+    assert_eq!(rows.len(), 7);
+    assert_eq!(
+        rows.iter().filter(|r| r["is_gap"] == json!(true)).count(),
+        3
+    );
     assert!(rows.iter().any(
         |r| r["block_start"] == "2024-12-01T10:00:00+00:00" && r["total_tokens"] == json!(485)
     ));
     assert!(v["totals"]["total_tokens"] == json!(1285));
     assert_eq!(v["model_breakdowns"].as_array().unwrap().len(), 4);
 }
+
+#[test]
+fn duplicate_entries_are_counted_once() {
+    let v = run_json(&["--data-dir", "tests/fixtures/dedup", "sessions", "--json"]);
+    assert_eq!(v["duplicates_removed"], json!(3));
+    assert_eq!(v["totals"]["total_tokens"], json!(915));
+    let rows = v["rows"].as_array().unwrap();
+    let original = rows
+        .iter()
+        .find(|r| r["session_id"] == "sess-gamma-1")
+        .unwrap();
+    assert_eq!(original["total_tokens"], json!(450));
+}
+
+#[test]
+fn event_index_is_reused_and_cleared() {
+    let cache_home = std::env::temp_dir().join(format!("ccusage-index-{}", std::process::id()));
+    let run = |args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        cmd.env("XDG_CACHE_HOME", &cache_home)
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+    let args = ["--data-dir", "tests/fixtures", "daily", "--json"];
+    let first = run(&args);
+    assert!(cache_home.join("ccusage-rs/events.json").exists());
+    let second = run(&args);
+    assert_eq!(first, second);
+
+    let cleared = String::from_utf8(run(&["cache", "clear"])).unwrap();
+    assert!(cleared.contains("events.json"));
+    assert!(!cache_home.join("ccusage-rs/events.json").exists());
+
+    // `no_cache` in the config skips the index like --no-cache
+    let config = cache_home.join("config.json");
+    std::fs::write(&config, r#"{ "defaults": { "no_cache": true } }"#).unwrap();
+    let uncached = run(&[
+        "--data-dir",
+        "tests/fixtures",
+        "--config",
+        config.to_str().unwrap(),
+        "daily",
+        "--json",
+    ]);
+    assert_eq!(uncached, first);
+    assert!(!cache_home.join("ccusage-rs/events.json").exists());
+    let _ = std::fs::remove_dir_all(&cache_home);
+}
+
+#[test]
+fn final_line_without_newline_is_counted() {
+    let total = |extra: &[&str]| -> Value {
+        let mut args = vec!["--data-dir", "tests/fixtures/unterminated"];
+        args.extend_from_slice(&["daily", "--json", "--offline"]);
+        args.extend_from_slice(extra);
+        run_json(&args)["totals"]["total_tokens"].clone()
+    };
+    assert_eq!(total(&["--no-cache"]), json!(160));
+    // The unterminated line stays out of the index but is re-read on every run
+    assert_eq!(total(&[]), json!(160));
+    assert_eq!(total(&[]), json!(160));
+}
+
+#[test]
+fn transcript_rewritten_to_a_larger_size_is_read_again() {
+    let dir = cache_home().join("data/projects/-work-app");
+    std::fs::create_dir_all(&dir).unwrap();
+    let line = |tokens: u64| {
+        json!({
+            "timestamp": "2024-12-01T10:00:00Z",
+            "message": {
+                "model": "claude-3-5-haiku-20241022",
+                "usage": { "input_tokens": tokens, "output_tokens": 0 }
+            }
+        })
+        .to_string()
+            + "\n"
+    };
+    let data_dir = cache_home().join("data");
+    let total = || {
+        run_json(&[
+            "--data-dir",
+            data_dir.to_str().unwrap(),
+            "daily",
+            "--json",
+            "--offline",
+        ])["totals"]["total_tokens"]
+            .clone()
+    };
+    std::fs::write(dir.join("sess.jsonl"), line(100)).unwrap();
+    assert_eq!(total(), json!(100));
+    // Replaced rather than appended to: the indexed line is gone
+    std::fs::write(dir.join("sess.jsonl"), line(200) + &line(300)).unwrap();
+    assert_eq!(total(), json!(500));
+    // A true append still reads only the new tail
+    let mut appended = line(200) + &line(300);
+    appended.push_str(&line(400));
+    std::fs::write(dir.join("sess.jsonl"), appended).unwrap();
+    assert_eq!(total(), json!(900));
+}
+
+#[test]
+fn blocks_start_at_first_activity() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "blocks",
+        "--json",
+        "--session-length-hours",
+        "3",
+    ]);
+    let rows = v["rows"].as_array().unwrap();
+    // 10:00 and 12:30 share one window instead of splitting at the 12:00 clock slot
+    let first = &rows[0];
+    assert_eq!(first["block_start"], "2024-12-01T10:00:00+00:00");
+    assert_eq!(first["block_end"], "2024-12-01T13:00:00+00:00");
+    assert_eq!(first["last_activity"], "2024-12-01T12:30:00+00:00");
+    assert_eq!(first["total_tokens"], json!(485));
+    assert_eq!(rows[1]["is_gap"], json!(true));
+    assert_eq!(rows[1]["block_end"], "2024-12-01T23:00:00+00:00");
+}
+
+#[test]
+fn blocks_start_in_either_occurrence_of_a_repeated_hour() {
+    let dir = cache_home().join("data/projects/-work-app");
+    std::fs::create_dir_all(&dir).unwrap();
+    // 01:30 EDT and 01:40 EST, the night New York falls back
+    let lines: Vec<String> = ["2024-11-03T05:30:00Z", "2024-11-03T06:40:00Z"]
+        .iter()
+        .map(|timestamp| {
+            json!({
+                "timestamp": timestamp,
+                "message": {
+                    "model": "claude-3-5-haiku-20241022",
+                    "usage": { "input_tokens": 100, "output_tokens": 0 }
+                },
+                "costUSD": 0.01
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(dir.join("sess.jsonl"), lines.join("\n") + "\n").unwrap();
+
+    let data_dir = cache_home().join("data");
+    let v = run_json(&[
+        "--data-dir",
+        data_dir.to_str().unwrap(),
+        "--timezone",
+        "America/New_York",
+        "blocks",
+        "--json",
+        "--order",
+        "asc",
+        "--session-length-hours",
+        "1",
+        "--offline",
+    ]);
+    let starts: Vec<Value> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|r| r["is_gap"] != json!(true))
+        .map(|r| r["block_start"].clone())
+        .collect();
+    assert_eq!(
+        starts,
+        vec![
+            json!("2024-11-03T01:00:00-04:00"),
+            json!("2024-11-03T01:00:00-05:00")
+        ]
+    );
+}
+
+#[test]
+fn active_block_projects_burn_rate_to_its_end() {
+    let dir = cache_home().join("data/projects/-work-app");
+    std::fs::create_dir_all(&dir).unwrap();
+    let now = chrono::Utc::now();
+    // Only the second line falls within the 30-minute burn-rate window
+    let lines: Vec<String> = [(40, 1500, 0.3), (10, 3000, 0.6)]
+        .iter()
+        .map(|(minutes_ago, tokens, cost)| {
+            json!({
+                "timestamp": (now - chrono::Duration::minutes(*minutes_ago)).to_rfc3339(),
+                "message": {
+                    "model": "claude-3-5-haiku-20241022",
+                    "usage": { "input_tokens": tokens, "output_tokens": 0 }
+                },
+                "costUSD": cost
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(dir.join("sess.jsonl"), lines.join("\n") + "\n").unwrap();
+
+    let data_dir = cache_home().join("data");
+    let v = run_json(&[
+        "--data-dir",
+        data_dir.to_str().unwrap(),
+        "blocks",
+        "--json",
+        "--token-limit",
+        "10000",
+        "--offline",
+    ]);
+    let row = &v["rows"][0];
+    assert_eq!(row["is_active"], json!(true));
+    assert_eq!(row["total_tokens"], json!(4500));
+    let remaining = row["remaining_minutes"].as_i64().unwrap();
+    let burn = &v["burn_rate"];
+    assert_eq!(burn["window_minutes"], json!(30));
+    assert_eq!(burn["tokens_per_minute"], json!(100.0));
+    assert_eq!(burn["cost_per_hour"], json!(1.2));
+    assert_eq!(
+        burn["projected_total_tokens"],
+        json!(4500 + 100 * remaining)
+    );
+    let projected_cost = burn["projected_cost_usd"].as_f64().unwrap();
+    let expected_cost = 0.9 + 1.2 * remaining as f64 / 60.0;
+    assert!(
+        (projected_cost - expected_cost).abs() < 1e-6,
+        "{projected_cost}"
+    );
+    // 5500 more tokens at 100 per minute fit in the block's remaining time
+    assert!(burn["limit_reached_at"].is_string());
+}
+
+#[test]
+fn statusline_reads_hook_payload_from_stdin() {
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    let output = cmd
+        .env("XDG_CACHE_HOME", cache_home())
+        .args([
+            "--data-dir",
+            "tests/fixtures",
+            "statusline",
+            "--json",
+            "--no-cache",
+        ])
+        .write_stdin(
+            json!({
+                "session_id": "sess-alpha-1",
+                "transcript_path": "tests/fixtures/projects/alpha/sess-alpha-1.jsonl",
+                "model": { "id": "claude-3-5-sonnet-20241022", "display_name": "Sonnet" },
+                "cwd": "/work/alpha"
+            })
+            .to_string(),
+        )
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let v: Value = serde_json::from_slice(&output).expect("valid json output");
+    let session = &v["session"];
+    assert_eq!(session["session_id"], "sess-alpha-1");
+    assert_eq!(session["model"], "Sonnet");
+    assert_eq!(session["cwd"], "/work/alpha");
+    assert_eq!(session["session_cost_usd"], json!(0.4));
+}
+
+#[test]
+fn statusline_session_cost_keeps_entries_an_earlier_session_repeated() {
+    let dir = cache_home().join("data/projects/-work-app");
+    std::fs::create_dir_all(&dir).unwrap();
+    let now = chrono::Utc::now();
+    let line = |minutes_ago: i64, id: &str, tokens: u64, cost: f64| {
+        json!({
+            "timestamp": (now - chrono::Duration::minutes(minutes_ago)).to_rfc3339(),
+            "requestId": format!("req_{id}"),
+            "message": {
+                "id": format!("msg_{id}"),
+                "model": "claude-3-5-haiku-20241022",
+                "usage": { "input_tokens": tokens, "output_tokens": 0 }
+            },
+            "costUSD": cost
+        })
+        .to_string()
+    };
+    let earlier = [line(50, "00", 100, 0.1), line(30, "01", 100, 0.1)].join("\n") + "\n";
+    std::fs::write(dir.join("sess-old.jsonl"), earlier).unwrap();
+    // The resumed session repeats the earlier entry before its own
+    let resumed = [line(30, "01", 100, 0.1), line(5, "02", 200, 0.2)].join("\n") + "\n";
+    std::fs::write(dir.join("sess-new.jsonl"), resumed).unwrap();
+
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    let output = cmd
+        .env("XDG_CACHE_HOME", cache_home())
+        .args([
+            "--data-dir",
+            cache_home().join("data").to_str().unwrap(),
+            "statusline",
+            "--json",
+            "--offline",
+        ])
+        .write_stdin(
+            json!({
+                "session_id": "sess-new",
+                "transcript_path": dir.join("sess-new.jsonl"),
+            })
+            .to_string(),
+        )
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let v: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(v["session"]["session_cost_usd"], json!(0.3));
+    // The day's total still counts the shared entry once
+    assert_eq!(v["totals"]["total_tokens"], json!(400));
+    assert_eq!(v["duplicates_removed"], json!(1));
+}
+
+#[test]
+fn statusline_hook_reads_only_the_appended_transcript_lines() {
+    let dir = cache_home().join("data/projects/-work-app");
+    std::fs::create_dir_all(&dir).unwrap();
+    let now = chrono::Utc::now();
+    let line = |id: &str, tokens: u64| {
+        json!({
+            "timestamp": (now - chrono::Duration::minutes(5)).to_rfc3339(),
+            "requestId": format!("req_{id}"),
+            "message": {
+                "id": format!("msg_{id}"),
+                "model": "claude-3-5-haiku-20241022",
+                "usage": { "input_tokens": tokens, "output_tokens": 0 }
+            }
+        })
+        .to_string()
+            + "\n"
+    };
+    std::fs::write(dir.join("sess-other.jsonl"), line("00", 100)).unwrap();
+    let transcript = dir.join("sess-hook.jsonl");
+    std::fs::write(&transcript, line("01", 200)).unwrap();
+
+    let totals = || -> Value {
+        let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        let output = cmd
+            .env("XDG_CACHE_HOME", cache_home())
+            .args(["--data-dir", cache_home().join("data").to_str().unwrap()])
+            .args(["statusline", "--json", "--offline"])
+            .write_stdin(
+                json!({ "session_id": "sess-hook", "transcript_path": transcript }).to_string(),
+            )
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&output).unwrap()
+    };
+    assert_eq!(totals()["totals"]["total_tokens"], json!(300));
+    // The hook keeps its own cache instead of indexing every transcript
+    assert!(cache_home().join("ccusage-rs/statusline.json").exists());
+    assert!(!cache_home().join("ccusage-rs/events.json").exists());
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&transcript)
+        .unwrap();
+    std::io::Write::write_all(&mut file, line("02", 400).as_bytes()).unwrap();
+    let v = totals();
+    assert_eq!(v["totals"]["total_tokens"], json!(700));
+    assert_eq!(v["session"]["session_id"], json!("sess-hook"));
+}
+
+#[test]
+fn statusline_cache_is_kept_per_data_dir() {
+    let root = cache_home().join("data");
+    let now = chrono::Utc::now().to_rfc3339();
+    for (name, tokens) in [("a", 1000), ("b", 7)] {
+        let dir = root.join(name).join("projects/-work-app");
+        std::fs::create_dir_all(&dir).unwrap();
+        let line = json!({
+            "timestamp": now,
+            "message": {
+                "model": "claude-3-5-haiku-20241022",
+                "usage": { "input_tokens": tokens, "output_tokens": 0 }
+            }
+        });
+        std::fs::write(dir.join("sess.jsonl"), format!("{line}\n")).unwrap();
+    }
+    let today_tokens = |name: &str| -> Value {
+        let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        let output = cmd
+            .env("XDG_CACHE_HOME", cache_home())
+            .args(["--data-dir", root.join(name).to_str().unwrap()])
+            .args(["statusline", "--json", "--offline"])
+            .write_stdin(json!({ "session_id": "other" }).to_string())
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let v: Value = serde_json::from_slice(&output).unwrap();
+        v["totals"]["total_tokens"].clone()
+    };
+    assert_eq!(today_tokens("a"), json!(1000));
+    // A fresh cache for another data dir is not reused
+    assert_eq!(today_tokens("b"), json!(7));
+}
+
+#[test]
+fn statusline_does_not_wait_on_idle_stdin() {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"))
+        .env("XDG_CACHE_HOME", cache_home())
+        .args(["--data-dir", "tests/fixtures", "statusline", "--offline"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    // Hold the pipe open without writing, like a script or cron job
+    let _stdin = child.stdin.take();
+    let started = std::time::Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if started.elapsed() > std::time::Duration::from_secs(10) {
+            let _ = child.kill();
+            panic!("statusline blocked on stdin");
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn statusline_renders_format_template() {
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    let output = cmd
+        .env("XDG_CACHE_HOME", cache_home())
+        .env("NO_COLOR", "1")
+        .args([
+            "--data-dir",
+            "tests/fixtures",
+            "statusline",
+            "--format",
+            "{date} {total} {cost|>1:red|green} {{x}}",
+        ])
+        .write_stdin("")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "2024-12-03 495 $0.40 {x}\n"
+    );
+
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    cmd.env("XDG_CACHE_HOME", cache_home())
+        .args([
+            "--data-dir",
+            "tests/fixtures",
+            "statusline",
+            "--format",
+            "{nope}",
+        ])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn statusline_thresholds_pick_colors_when_forced() {
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    let output = cmd
+        .env("XDG_CACHE_HOME", cache_home())
+        .env("CLICOLOR_FORCE", "1")
+        .env_remove("NO_COLOR")
+        .args([
+            "--data-dir",
+            "tests/fixtures",
+            "statusline",
+            "--format",
+            "{cost|>0.3:red|green} {cost|>1:red|green}",
+            "--offline",
+        ])
+        .write_stdin("")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\x1b[31m$0.40\x1b[0m \x1b[32m$0.40\x1b[0m\n"
+    );
+}
+
+#[test]
+fn auto_cost_mode_recalculates_a_zero_logged_cost() {
+    let total = |mode: &str| {
+        run_json(&[
+            "--data-dir",
+            "tests/fixtures/zero-cost",
+            "--cost-mode",
+            mode,
+            "daily",
+            "--json",
+            "--offline",
+        ])["totals"]["cost_usd"]
+            .clone()
+    };
+    // One entry logs $0 and one logs $0.50; each would calculate to $0.80
+    assert_eq!(total("auto"), json!(1.3));
+    assert_eq!(total("prefer-field"), json!(0.5));
+    assert_eq!(total("calculate"), json!(1.6));
+}
+
+#[test]
+fn calculated_costs_bill_each_token_category_once() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "--offline",
+        "--cost-mode",
+        "calculate",
+        "daily",
+        "--json",
+        "--order",
+        "asc",
+    ]);
+    let row = &v["rows"][2];
+    assert_eq!(row["key"], "2024-12-03");
+    // Each category is rounded to whole micro-dollars and the total is their exact sum
+    assert_eq!(row["input_cost"], json!(0.0045));
+    assert_eq!(row["output_cost"], json!(0.01125));
+    assert_eq!(row["cache_write_cost"], json!(0.000563));
+    assert_eq!(row["cache_read_cost"], json!(0.000023));
+    assert_eq!(row["cost_usd"], json!(0.016336));
+}
+
+#[test]
+fn totals_reconcile_exactly_across_reports() {
+    let totals = |cmd: &str| {
+        run_json(&["--data-dir", "tests/fixtures", cmd, "--json", "--offline"])["totals"].clone()
+    };
+    let daily = totals("daily");
+    assert_eq!(daily["cost_usd"], json!(1.05));
+    for cmd in ["weekly", "monthly", "sessions", "blocks"] {
+        let other = totals(cmd);
+        for field in [
+            "cost_usd",
+            "input_cost",
+            "output_cost",
+            "cache_write_cost",
+            "cache_read_cost",
+        ] {
+            assert_eq!(other[field], daily[field], "{cmd} {field}");
+        }
+    }
+}
+
+#[test]
+fn cache_writes_are_priced_per_ttl_tier() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/cache-tiers",
+        "--offline",
+        "--cost-mode",
+        "calculate",
+        "daily",
+        "--json",
+        "--breakdown",
+    ]);
+    let model = &v["model_breakdowns"][0];
+    // The last line's flat count exceeds its tiers by 300, billed as 5-minute writes
+    assert_eq!(model["cache_creation_tokens"], json!(4300));
+    assert_eq!(model["cache_creation_5m_tokens"], json!(1700));
+    assert_eq!(model["cache_creation_1h_tokens"], json!(2600));
+    // 5m writes at the cache-create rate, 1h writes at twice the input rate
+    let expected = (1700.0 * 3.75 + 2600.0 * 6.0) / 1e6;
+    let actual = v["totals"]["cache_write_cost"].as_f64().unwrap();
+    assert!((actual - expected).abs() < 1e-9);
+}
+
+#[test]
+fn prompts_above_200k_tokens_use_the_long_context_tier() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/long-context",
+        "--offline",
+        "--cost-mode",
+        "calculate",
+        "sessions",
+        "--json",
+    ]);
+    let totals = &v["totals"];
+    assert_eq!(totals["long_context_requests"], json!(1));
+    // 190k prompt tokens at base rates: 150k x $3 + 40k x $0.30 + 1k x $15 per million
+    let base = 0.45 + 0.012 + 0.015;
+    // 210k prompt tokens bill the whole request at premium rates: $6 / $0.60 / $22.50
+    let premium = 0.72 + 0.054 + 0.045;
+    let cost = totals["cost_usd"].as_f64().unwrap();
+    assert!((cost - (base + premium)).abs() < 1e-9, "cost {cost}");
+}
+
+#[test]
+fn config_pricing_overrides_aliases_and_long_context_tier() {
+    let v = run_json(&[
+        "--config",
+        "tests/fixtures/pricing-config/config.json",
+        "--data-dir",
+        "tests/fixtures/pricing-config",
+        "--offline",
+        "--cost-mode",
+        "calculate",
+        "daily",
+        "--json",
+    ]);
+    let totals = &v["totals"];
+    // 0.003 at base rates, 1.02 at long-context rates, 0.002 at the overridden opus rate
+    let cost = totals["cost_usd"].as_f64().unwrap();
+    assert!((cost - 1.025).abs() < 1e-9, "cost {cost}");
+    assert_eq!(totals["long_context_requests"], json!(1));
+}
+
+#[test]
+fn pricing_show_reports_matched_key_and_source() {
+    let v = run_json(&[
+        "pricing",
+        "show",
+        "claude-sonnet-4-5-20250929",
+        "--offline",
+        "--json",
+    ]);
+    assert_eq!(v["matched_key"], "claude-sonnet-4");
+    assert_eq!(v["origin"], "bundled");
+    assert_eq!(v["pricing"]["input_above_200k_per_million"], json!(6.0));
+
+    let v = run_json(&[
+        "--config",
+        "tests/fixtures/pricing-config/config.json",
+        "pricing",
+        "show",
+        "my-proxy/sonnet",
+        "--offline",
+        "--json",
+    ]);
+    assert_eq!(v["alias_of"], "custom-sonnet");
+    assert_eq!(v["origin"], "config");
+
+    // A partial override keeps the data's other rates
+    let v = run_json(&[
+        "--config",
+        "tests/fixtures/pricing-config/config.json",
+        "pricing",
+        "show",
+        "claude-3-opus-20240229",
+        "--offline",
+        "--json",
+    ]);
+    assert_eq!(v["origin"], "config");
+    assert_eq!(v["pricing"]["input_per_million"], json!(1.0));
+    assert_eq!(v["pricing"]["cache_create_per_million"], json!(18.75));
+    assert_eq!(v["pricing"]["cache_create_1h_per_million"], json!(30.0));
+    assert_eq!(v["pricing"]["cache_read_per_million"], json!(1.5));
+}
+
+#[test]
+fn pricing_import_is_used_without_network() {
+    let cache_home = std::env::temp_dir().join(format!("ccusage-pricing-{}", std::process::id()));
+    let run = |args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        cmd.env("XDG_CACHE_HOME", &cache_home)
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+    run(&[
+        "pricing",
+        "import",
+        "tests/fixtures/pricing-import/litellm.json",
+    ]);
+    let snapshot: Value =
+        serde_json::from_slice(&std::fs::read(cache_home.join("ccusage-rs/pricing.json")).unwrap())
+            .unwrap();
+    assert_eq!(snapshot["imported"], json!(true));
+    assert!(snapshot["fetched_at"].is_string());
+
+    let listed: Value = serde_json::from_slice(&run(&["pricing", "list", "--json"])).unwrap();
+    assert_eq!(
+        listed["claude-imported-1"]["output_per_million"],
+        json!(8.0)
+    );
+    assert_eq!(
+        listed["claude-imported-2"]["input_above_200k_per_million"],
+        json!(2.0)
+    );
+
+    // Clearing the cache keeps an imported table unless asked to drop pricing too
+    run(&["cache", "clear"]);
+    assert!(cache_home.join("ccusage-rs/pricing.json").exists());
+    run(&["cache", "clear", "--pricing"]);
+    assert!(!cache_home.join("ccusage-rs/pricing.json").exists());
+    let _ = std::fs::remove_dir_all(&cache_home);
+}
+
+#[test]
+fn archived_pricing_applies_rates_in_effect_on_event_day() {
+    let cache_home = std::env::temp_dir().join(format!("ccusage-history-{}", std::process::id()));
+    let history = cache_home.join("ccusage-rs/pricing-history");
+    std::fs::create_dir_all(&history).unwrap();
+    for name in ["2024-10-01.json", "2025-01-01.json"] {
+        std::fs::copy(
+            format!("tests/fixtures/dated-pricing/history/{name}"),
+            history.join(name),
+        )
+        .unwrap();
+    }
+    let run = |extra: &[&str]| -> Value {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        let output = cmd
+            .env("XDG_CACHE_HOME", &cache_home)
+            .args([
+                "--data-dir",
+                "tests/fixtures/dated-pricing",
+                "--offline",
+                "--cost-mode",
+                "calculate",
+            ])
+            .args(extra)
+            .args(["daily", "--json", "--order", "asc"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&output).unwrap()
+    };
+    let costs = |v: &Value| -> Vec<f64> {
+        v["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["cost_usd"].as_f64().unwrap())
+            .collect()
+    };
+    let close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-9);
+
+    // The oldest archive also covers earlier usage; each applies until the next change
+    let v = run(&[]);
+    assert!(close(&costs(&v), &[0.06, 0.06, 0.12]), "{:?}", costs(&v));
+
+    // Dated config entries win inside their window only
+    let v = run(&["--config", "tests/fixtures/dated-pricing/config.json"]);
+    assert!(close(&costs(&v), &[0.002, 0.06, 0.12]), "{:?}", costs(&v));
+
+    // Archives keep Claude rates only
+    let archived: Value =
+        serde_json::from_slice(&std::fs::read(history.join("2024-10-01.json")).unwrap()).unwrap();
+    let models: Vec<&String> = archived["models"].as_object().unwrap().keys().collect();
+    assert_eq!(models, ["claude-3-opus-20240229"]);
+    let _ = std::fs::remove_dir_all(&cache_home);
+}
+
+#[test]
+fn unpriced_models_are_reported_and_fail_strict_mode() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/unpriced",
+        "sessions",
+        "--json",
+        "--offline",
+    ]);
+    // The entry with a logged costUSD is not free, so only two requests count
+    assert_eq!(
+        v["unpriced_models"],
+        json!([{ "model": "claude-future-9", "requests": 2, "total_tokens": 1800 }])
+    );
+
+    let v = run_json(&["--data-dir", "tests/fixtures", "daily", "--json"]);
+    assert_eq!(v["unpriced_models"], json!([]));
+
+    for report in [
+        "daily",
+        "monthly",
+        "sessions",
+        "blocks",
+        "statusline",
+        "audit",
+        "branches",
+        "tools",
+        "errors",
+        "versions",
+    ] {
+        let v = run_json(&[
+            "--data-dir",
+            "tests/fixtures/unpriced",
+            report,
+            "--json",
+            "--offline",
+        ]);
+        assert_eq!(
+            v["unpriced_models"][0]["model"], "claude-future-9",
+            "{report}"
+        );
+
+        let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        let output = cmd
+            .env("XDG_CACHE_HOME", cache_home())
+            .args([
+                "--data-dir",
+                "tests/fixtures/unpriced",
+                report,
+                "--offline",
+                "--strict-pricing",
+            ])
+            .write_stdin("")
+            .assert()
+            .failure()
+            .get_output()
+            .stderr
+            .clone();
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("claude-future-9"),
+            "{report}"
+        );
+    }
+}
+
+#[test]
+fn audit_compares_logged_and_calculated_costs() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/unpriced",
+        "audit",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(v["kind"], "audit");
+    // Only the entry with a logged costUSD counts towards the deltas
+    assert_eq!(v["totals"]["events"], 1);
+    assert_eq!(v["totals"]["missing_cost_usd_events"], 3);
+    assert_eq!(v["missing_cost_usd"]["events"], 3);
+    assert_eq!(v["missing_cost_usd"]["sample"].as_array().unwrap().len(), 3);
+    // A logged cost the calculation cannot reproduce is always out of tolerance
+    assert_eq!(v["over_tolerance"], json!(["claude-future-9"]));
+
+    // Haiku's logged cost drifts 0.5% from the calculated one, Sonnet's 3%
+    let over = |tolerance: &str| {
+        let v = run_json(&[
+            "--data-dir",
+            "tests/fixtures/audit",
+            "audit",
+            "--json",
+            "--offline",
+            "--tolerance",
+            tolerance,
+        ]);
+        assert_eq!(v["totals"]["events"], 2);
+        assert_eq!(v["missing_cost_usd"]["events"], 0);
+        v["over_tolerance"].clone()
+    };
+    assert_eq!(
+        over("0.1"),
+        json!(["claude-3-5-haiku-20241022", "claude-3-5-sonnet-20241022"])
+    );
+    assert_eq!(over("2"), json!(["claude-3-5-sonnet-20241022"]));
+    assert_eq!(over("5"), json!([]));
+
+    let v = run_json(&[
+        "--config",
+        "tests/fixtures/audit/config.json",
+        "--data-dir",
+        "tests/fixtures/audit",
+        "audit",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(v["tolerance_percent"], json!(2.0));
+}
+
+#[test]
+fn costs_convert_at_each_days_exchange_rate() {
+    let run = |currency: &str, locale: &str| {
+        run_json(&[
+            "--data-dir",
+            "tests/fixtures",
+            "--exchange-rates",
+            "tests/fixtures/currency/rates.json",
+            "--currency",
+            currency,
+            "--locale",
+            locale,
+            "daily",
+            "--json",
+            "--order",
+            "asc",
+            "--offline",
+        ])
+    };
+    let v = run("eur", "de");
+    assert_eq!(v["currency"], "EUR");
+    let converted: Vec<Value> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["converted_cost"].clone())
+        .collect();
+    // The earliest dated rate also covers the first day
+    assert_eq!(converted, vec![json!(0.405), json!(0.18), json!(0.38)]);
+    assert_eq!(v["totals"]["converted_cost"], json!(0.965));
+    assert_eq!(v["totals"]["cost_usd"], json!(1.05));
+
+    let v = run("JPY", "ja");
+    assert_eq!(v["totals"]["converted_cost"], json!(157.5));
+
+    // 2024-12-03T01:00Z is still Dec 2 in Los Angeles, so it takes that day's rate
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "--exchange-rates",
+        "tests/fixtures/currency/rates.json",
+        "--currency",
+        "EUR",
+        "--timezone",
+        "America/Los_Angeles",
+        "daily",
+        "--json",
+        "--order",
+        "asc",
+        "--offline",
+    ]);
+    let converted: Vec<Value> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["converted_cost"].clone())
+        .collect();
+    assert_eq!(converted, vec![json!(0.405), json!(0.54)]);
+
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "daily",
+        "--json",
+        "--offline",
+    ]);
+    assert!(v.get("currency").is_none());
+    assert!(v["totals"].get("converted_cost").is_none());
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    cmd.env("XDG_CACHE_HOME", cache_home())
+        .args([
+            "--data-dir",
+            "tests/fixtures",
+            "--exchange-rates",
+            "tests/fixtures/currency/rates.json",
+            "--currency",
+            "GBP",
+            "daily",
+            "--offline",
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn plan_report_compares_cycles_with_the_fee() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "plan",
+        "--plan",
+        "pro",
+        "--renewal-day",
+        "2",
+        "--until",
+        "2024-12-31",
+        "--json",
+        "--offline",
+    ]);
+    let cycles = v["cycles"].as_array().unwrap();
+    let ranges: Vec<(&str, &str)> = cycles
+        .iter()
+        .map(|c| {
+            (
+                c["cycle_start"].as_str().unwrap(),
+                c["cycle_end"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![("2024-11-02", "2024-12-01"), ("2024-12-02", "2025-01-01")]
+    );
+    assert_eq!(cycles[1]["api_cost_usd"], json!(0.6));
+    assert_eq!(cycles[1]["net_usd"], json!(-19.4));
+    assert!(cycles[1].get("break_even_date").is_none());
+    assert_eq!(v["totals"]["plan_fee_usd"], json!(40.0));
+
+    // Cycles without usage still cost the fee
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "plan",
+        "--plan",
+        "pro",
+        "--renewal-day",
+        "2",
+        "--since",
+        "2024-10-15",
+        "--until",
+        "2025-01-15",
+        "--json",
+        "--offline",
+    ]);
+    let cycles = v["cycles"].as_array().unwrap();
+    assert_eq!(cycles.len(), 4);
+    assert_eq!(cycles[0]["cycle_start"], "2024-10-02");
+    assert_eq!(cycles[0]["api_cost_usd"], json!(0.0));
+    assert_eq!(cycles[0]["net_usd"], json!(-20.0));
+    // The cycle after the last usage is fee only
+    assert_eq!(cycles[3]["cycle_start"], "2025-01-02");
+    assert_eq!(cycles[3]["api_cost_usd"], json!(0.0));
+    assert_eq!(cycles[3]["net_usd"], json!(-20.0));
+    assert_eq!(v["totals"]["plan_fee_usd"], json!(80.0));
+
+    // Without --until the cycles run up to the current one
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "plan",
+        "--plan",
+        "pro",
+        "--renewal-day",
+        "2",
+        "--json",
+        "--offline",
+    ]);
+    let cycles = v["cycles"].as_array().unwrap();
+    let current = cycles.last().unwrap();
+    assert_eq!(current["is_current"], json!(true));
+    assert_eq!(current["api_cost_usd"], json!(0.0));
+    assert_eq!(
+        v["totals"]["plan_fee_usd"],
+        json!(20.0 * cycles.len() as f64)
+    );
+
+    // A renewal day past the month's end falls on its last day
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures",
+        "plan",
+        "--plan",
+        "custom",
+        "--monthly-price",
+        "0.5",
+        "--renewal-day",
+        "31",
+        "--until",
+        "2024-12-31",
+        "--json",
+        "--offline",
+    ]);
+    let cycle = &v["cycles"][0];
+    assert_eq!(cycle["cycle_start"], "2024-11-30");
+    assert_eq!(cycle["cycle_end"], "2024-12-30");
+    assert_eq!(cycle["value_multiplier"], json!(2.1));
+    assert_eq!(cycle["break_even_date"], "2024-12-02");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+    cmd.env("XDG_CACHE_HOME", cache_home())
+        .args([
+            "--data-dir",
+            "tests/fixtures",
+            "plan",
+            "--plan",
+            "custom",
+            "--offline",
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn monthly_report_buckets_by_billing_cycle() {
+    let keys = |day: &str| -> Vec<String> {
+        let v = run_json(&[
+            "--data-dir",
+            "tests/fixtures",
+            "monthly",
+            "--cycle-start-day",
+            day,
+            "--order",
+            "asc",
+            "--json",
+            "--offline",
+        ]);
+        v["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["key"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(keys("1"), vec!["2024-12"]);
+    assert_eq!(
+        keys("2"),
+        vec!["2024-11-02..2024-12-01", "2024-12-02..2025-01-01"]
+    );
+    // Cycles starting on the 31st begin on the last day of shorter months
+    assert_eq!(keys("31"), vec!["2024-11-30..2024-12-30"]);
+}
+
+#[test]
+fn projects_are_labelled_by_working_directory() {
+    let home = std::env::temp_dir().join(format!("ccusage-cwd-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&home);
+    let repo = home.join("Work").join("my-app");
+    std::fs::create_dir_all(&repo).unwrap();
+    // Claude names the transcript directory after the path with separators as dashes
+    let encoded = repo.to_str().unwrap().replace('/', "-");
+    let transcripts = home.join("claude").join("projects").join(encoded);
+    std::fs::create_dir_all(&transcripts).unwrap();
+    let line = |session: &str, cwd: Option<&str>| {
+        let mut record = json!({
+            "timestamp": "2024-12-05T10:00:00Z",
+            "sessionId": session,
+            "message": {
+                "usage": { "input_tokens": 100, "output_tokens": 50 },
+                "model": "claude-3-5-sonnet-20241022"
+            },
+            "costUSD": 0.01
+        });
+        if let Some(cwd) = cwd {
+            record["cwd"] = json!(cwd);
+        }
+        format!("{record}\n")
+    };
+    std::fs::write(
+        transcripts.join("with-cwd.jsonl"),
+        line("with-cwd", repo.to_str()),
+    )
+    .unwrap();
+    // Older lines without cwd fall back to decoding the directory name
+    std::fs::write(transcripts.join("no-cwd.jsonl"), line("no-cwd", None)).unwrap();
+
+    let data_dir = home.join("claude");
+    let run = |extra: &[&str]| -> Value {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ccusage"));
+        let output = cmd
+            .env("HOME", &home)
+            .env("XDG_CACHE_HOME", home.join("cache"))
+            .args(["--data-dir", data_dir.to_str().unwrap(), "sessions"])
+            .args(extra)
+            .args(["--json", "--offline"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&output).unwrap()
+    };
+    let projects = |v: &Value| -> Vec<String> {
+        let rows = v["rows"].as_array().unwrap();
+        rows.iter()
+            .map(|r| r["project"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(projects(&run(&[])), vec!["~/Work/my-app", "~/Work/my-app"]);
+    let root = home.join("Work");
+    let v = run(&["--project-root", root.to_str().unwrap()]);
+    assert_eq!(projects(&v), vec!["my-app", "my-app"]);
+    let v = run(&[
+        "--project-root",
+        root.to_str().unwrap(),
+        "--project",
+        "my-app",
+    ]);
+    assert_eq!(v["rows"].as_array().unwrap().len(), 2);
+    let v = run(&["--project", repo.to_str().unwrap()]);
+    assert_eq!(v["rows"].as_array().unwrap().len(), 1);
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn branches_report_groups_by_repository_and_branch() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/branches",
+        "branches",
+        "--json",
+        "--offline",
+    ]);
+    let rows: Vec<(Value, Value, Value, Value)> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["repository"].clone(),
+                r["branch"].clone(),
+                r["sessions"].clone(),
+                r["cost_usd"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (
+                json!("/work/shop"),
+                json!("feature-x"),
+                json!(2),
+                json!(0.3)
+            ),
+            (json!("/work/shop"), json!("main"), json!(1), json!(0.1)),
+            (json!("/work/docs"), Value::Null, json!(1), json!(0.02)),
+        ]
+    );
+    assert_eq!(v["totals"]["cost_usd"], json!(0.42));
+
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/branches",
+        "daily",
+        "--branch",
+        "feature-x",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(v["totals"]["cost_usd"], json!(0.3));
+    assert_eq!(v["rows"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn sidechain_usage_can_be_split_or_filtered() {
+    let dir = "tests/fixtures/sidechain";
+    let v = run_json(&[
+        "--data-dir",
+        dir,
+        "daily",
+        "--sidechain",
+        "split",
+        "--json",
+        "--offline",
+    ]);
+    let rows = v["rows"].as_array().unwrap();
+    let first = &rows.iter().find(|r| r["key"] == "2024-12-11").unwrap()["sidechain"];
+    assert_eq!(first["main"]["cost_usd"], json!(0.2));
+    assert_eq!(first["main"]["total_tokens"], json!(150));
+    assert_eq!(first["subagent"]["cost_usd"], json!(0.08));
+    assert_eq!(first["subagent"]["total_tokens"], json!(80));
+    assert_eq!(v["totals"]["sidechain"]["main"]["cost_usd"], json!(0.3));
+    assert_eq!(
+        v["totals"]["sidechain"]["subagent"]["cost_usd"],
+        json!(0.08)
+    );
+    assert_eq!(v["totals"]["cost_usd"], json!(0.38));
+
+    let v = run_json(&[
+        "--data-dir",
+        dir,
+        "sessions",
+        "--sidechain",
+        "split",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(
+        v["rows"][0]["sidechain"]["subagent"]["input_tokens"],
+        json!(50)
+    );
+
+    let v = run_json(&[
+        "--data-dir",
+        dir,
+        "blocks",
+        "--sidechain",
+        "split",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(
+        v["totals"]["sidechain"]["subagent"]["cost_usd"],
+        json!(0.08)
+    );
+
+    let v = run_json(&[
+        "--data-dir",
+        dir,
+        "daily",
+        "--sidechain",
+        "only",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(v["totals"]["cost_usd"], json!(0.08));
+    assert!(v["totals"].get("sidechain").is_none());
+
+    let v = run_json(&[
+        "--data-dir",
+        dir,
+        "daily",
+        "--sidechain",
+        "exclude",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(v["totals"]["cost_usd"], json!(0.3));
+    assert_eq!(v["rows"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn tools_report_attributes_turns_to_the_tools_they_called() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/tools",
+        "tools",
+        "--json",
+        "--offline",
+    ]);
+    let rows: Vec<(Value, Value, Value, Value)> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["tool"].clone(),
+                r["invocations"].clone(),
+                r["total_tokens"].clone(),
+                r["cost_usd"].clone(),
+            )
+        })
+        .collect();
+    // Streamed content blocks of one message are merged before the turn is split
+    assert_eq!(
+        rows,
+        vec![
+            (json!("Bash"), json!(2), json!(135), json!(0.25)),
+            (
+                json!("mcp__github__create_issue"),
+                json!(1),
+                json!(75),
+                json!(0.15)
+            ),
+            (json!("Read"), json!(2), json!(60), json!(0.06)),
+            (
+                json!("mcp__github__get_issue"),
+                json!(1),
+                json!(30),
+                json!(0.03)
+            ),
+        ]
+    );
+    assert_eq!(
+        v["rows"][0]["by_day"],
+        json!({"2024-12-11": 1, "2024-12-12": 1})
+    );
+    assert_eq!(
+        v["rows"][0]["by_session"],
+        json!({"sess-tools-1": 1, "sess-tools-2": 1})
+    );
+
+    let github = &v["mcp_servers"][0];
+    assert_eq!(github["server"], json!("github"));
+    assert_eq!(
+        github["tools"],
+        json!(["mcp__github__create_issue", "mcp__github__get_issue"])
+    );
+    assert_eq!(github["invocations"], json!(2));
+    assert_eq!(github["cost_usd"], json!(0.18));
+
+    assert_eq!(v["totals"]["invocations"], json!(6));
+    assert_eq!(v["totals"]["cost_usd"], json!(0.49));
+    assert_eq!(v["without_tools"]["turns"], json!(1));
+    assert_eq!(v["without_tools"]["cost_usd"], json!(0.05));
+}
+
+#[test]
+fn errors_report_counts_api_errors_and_limit_hits() {
+    let dir = "tests/fixtures/errors";
+    let v = run_json(&["--data-dir", dir, "errors", "--json", "--offline"]);
+    assert_eq!(
+        v["totals"],
+        json!({"api_errors": 1, "overloaded": 1, "usage_limits": 3, "total": 5})
+    );
+    let kinds: Vec<&str> = v["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "overloaded",
+            "api_error",
+            "usage_limit",
+            "usage_limit",
+            "usage_limit"
+        ]
+    );
+    // Epoch suffix in older transcripts; "resets 2pm (zone)" in newer ones
+    assert_eq!(
+        v["events"][2]["resets_at"],
+        json!("2024-12-11T16:00:00+00:00")
+    );
+    assert_eq!(
+        v["events"][3]["resets_at"],
+        json!("2024-12-12T19:00:00+00:00")
+    );
+    assert_eq!(
+        v["events"][4]["resets_at"],
+        json!("2024-12-13T15:00:00+00:00")
+    );
+
+    assert_eq!(v["days"][0]["date"], json!("2024-12-11"));
+    assert_eq!(v["days"][0]["total"], json!(3));
+    assert_eq!(v["days"][1]["usage_limits"], json!(1));
+    // An error outside any usage block counts for its day only
+    assert_eq!(v["days"][2]["usage_limits"], json!(1));
+    assert_eq!(v["blocks"].as_array().unwrap().len(), 2);
+
+    let block = &v["blocks"][0];
+    assert_eq!(block["block_start"], json!("2024-12-11T10:00:00+00:00"));
+    assert_eq!(block["total"], json!(3));
+    assert_eq!(block["first_limit_hit"], json!("2024-12-11T12:00:00+00:00"));
+    assert_eq!(block["cost_usd"], json!(0.2));
+
+    let v = run_json(&["--data-dir", dir, "blocks", "--json", "--offline"]);
+    let rows: Vec<&Value> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|r| r["is_gap"] == json!(false))
+        .collect();
+    assert_eq!(rows[0]["errors"]["usage_limits"], json!(1));
+    assert_eq!(
+        rows[1]["limit_resets_at"],
+        json!("2024-12-12T19:00:00+00:00")
+    );
+    // Error lines without usage neither open blocks nor count as usage rows
+    assert_eq!(rows.len(), 2);
+    let v = run_json(&["--data-dir", dir, "daily", "--json", "--offline"]);
+    assert_eq!(v["rows"].as_array().unwrap().len(), 2);
+    assert_eq!(v["totals"]["total_tokens"], json!(210));
+    let v = run_json(&["--data-dir", dir, "tools", "--json", "--offline"]);
+    // Two turns plus the synthetic "No response requested." line, which is not an error
+    assert_eq!(v["without_tools"]["turns"], json!(3));
+
+    // A reset time without a zone is read in the report's timezone
+    let v = run_json(&[
+        "--data-dir",
+        dir,
+        "--timezone",
+        "Asia/Tokyo",
+        "errors",
+        "--json",
+        "--offline",
+    ]);
+    assert_eq!(
+        v["events"][4]["resets_at"],
+        json!("2024-12-14T15:00:00+09:00")
+    );
+}
+
+#[test]
+fn versions_report_tracks_cache_hits_per_release() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/versions",
+        "versions",
+        "--order",
+        "asc",
+        "--json",
+        "--offline",
+    ]);
+    let rows = v["rows"].as_array().unwrap();
+    let versions: Vec<&Value> = rows.iter().map(|r| &r["version"]).collect();
+    assert_eq!(versions, [&Value::Null, &json!("1.0.30"), &json!("1.0.31")]);
+
+    let old = &rows[1];
+    assert_eq!(old["sessions"], json!(2));
+    assert_eq!(old["first_seen"], json!("2024-12-10T10:00:00+00:00"));
+    assert_eq!(old["last_seen"], json!("2024-12-10T15:00:00+00:00"));
+    assert_eq!(old["cost_usd"], json!(0.15));
+    assert_eq!(old["cache_hit_ratio"], json!(0.4));
+
+    let new = &rows[2];
+    assert_eq!(new["cache_hit_ratio"], json!(0.8));
+    let change = new["cache_hit_ratio_change"].as_f64().unwrap();
+    assert!((change - 0.4).abs() < 1e-9);
+    assert_eq!(v["totals"]["cost_usd"], json!(0.24));
+}
+
+#[test]
+fn compressed_transcripts_are_read_like_plain_ones() {
+    let v = run_json(&[
+        "--data-dir",
+        "tests/fixtures/compressed",
+        "sessions",
+        "--json",
+        "--offline",
+    ]);
+    // Session ids drop both extensions; the archived gzip lacks a trailing newline
+    let rows: Vec<(Value, Value, Value)> = v["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["session_id"].clone(),
+                r["project"].clone(),
+                r["total_tokens"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (json!("sess-plain"), json!("/work/app"), json!(15)),
+            (json!("sess-zst"), json!("/work/app"), json!(60)),
+            (json!("sess-gz"), json!("/work/app"), json!(180)),
+        ]
+    );
+    assert_eq!(v["totals"]["cost_usd"], json!(0.35));
+}